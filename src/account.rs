@@ -1,9 +1,24 @@
 mod authorizer;
+mod config;
+mod dsl;
 mod errors;
+mod fx;
 mod models;
+mod money;
+mod shadow;
+mod trace;
 mod validations;
 
-pub use authorizer::Authorizer;
-pub use errors::OperationError;
-pub use models::{Account, AccountState, TX};
-pub use validations::{AccountRule, DuplicatedTx, HighFrequencySmallInterval, InsufficientLimit};
+pub use authorizer::{Authorizer, AuthorizerBuilder};
+pub use config::{Config, RulesConfig};
+pub use dsl::{parse_rules, DslError, DslRule};
+pub use errors::{OperationError, Violation};
+pub use fx::FxRates;
+pub use models::{Account, AccountState, Hold, MerchantCategory, TX};
+pub use money::{Currency, Decimal, Money};
+pub use shadow::ShadowSummary;
+pub use trace::RuleTrace;
+pub use validations::{
+    AccountRule, BlockedMerchantCategory, DailyLimit, DuplicatedTx, HighFrequencySmallInterval,
+    InsufficientLimit, MerchantLimit, MonthlyLimit,
+};
@@ -1,65 +1,471 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::sync::Arc;
 
-use crate::account::{Account, AccountState, OperationError, TX};
+use chrono::{DateTime, Utc};
+
+use crate::account::{
+    Account, AccountRule, AccountState, Config, Currency, FxRates, Hold, MerchantCategory, Money,
+    OperationError, RuleTrace, ShadowSummary, Violation, TX,
+};
 
 #[derive(Debug)]
 pub struct Authorizer {
-    account: Option<Account>,
+    accounts: HashMap<String, Account>,
+    config: Config,
+    fx_rates: FxRates,
+    custom_rules: Vec<Arc<dyn AccountRule>>,
+    shadow_rules: Vec<Arc<dyn AccountRule>>,
+    shadow_summary: ShadowSummary,
+    explain: bool,
+}
+
+/// Builds an [`Authorizer`], registering rules of its own on top of the configured ones for
+/// every account it creates.
+#[derive(Debug, Default)]
+pub struct AuthorizerBuilder {
+    config: Config,
+    fx_rates: FxRates,
+    custom_rules: Vec<Arc<dyn AccountRule>>,
+    shadow_rules: Vec<Arc<dyn AccountRule>>,
+    explain: bool,
+}
+
+impl AuthorizerBuilder {
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the rates used to convert transactions made in a currency other than the account's.
+    pub fn fx_rates(mut self, fx_rates: FxRates) -> Self {
+        self.fx_rates = fx_rates;
+        self
+    }
+
+    /// Registers a rule run, after the configured ones, for every transaction of every account.
+    pub fn rule(mut self, rule: impl AccountRule + 'static) -> Self {
+        self.custom_rules.push(Arc::new(rule));
+        self
+    }
+
+    /// Registers a rule run in shadow mode for every transaction of every account, reporting its
+    /// violations apart without declining the transaction.
+    pub fn shadow_rule(mut self, rule: impl AccountRule + 'static) -> Self {
+        self.shadow_rules.push(Arc::new(rule));
+        self
+    }
+
+    /// Attaches to every transaction result the verdict of each rule and the evidence behind it.
+    pub fn explain(mut self, explain: bool) -> Self {
+        self.explain = explain;
+        self
+    }
+
+    pub fn build(self) -> Authorizer {
+        Authorizer {
+            accounts: HashMap::new(),
+            config: self.config,
+            fx_rates: self.fx_rates,
+            custom_rules: self.custom_rules,
+            shadow_rules: self.shadow_rules,
+            shadow_summary: ShadowSummary::default(),
+            explain: self.explain,
+        }
+    }
+}
+
+impl Default for Authorizer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Authorizer {
     pub fn new() -> Self {
-        Self {
-            account: Option::None,
+        Self::with_config(Config::default())
+    }
+
+    pub fn with_config(config: Config) -> Self {
+        Self::builder().config(config).build()
+    }
+
+    pub fn builder() -> AuthorizerBuilder {
+        AuthorizerBuilder::default()
+    }
+
+    pub fn create_account(&mut self, account_id: String, account: Account) -> AccountState {
+        if self.accounts.contains_key(&account_id) {
+            return account
+                .to_invalid_state(vec![OperationError::AccountAlreadyInitialized])
+                .with_account_id(&account_id);
         }
+
+        let state = account.to_state().with_account_id(&account_id);
+
+        self.accounts.insert(
+            account_id,
+            account
+                .with_rules_config(self.config.rules.clone())
+                .with_custom_rules(self.custom_rules.clone())
+                .with_shadow_rules(self.shadow_rules.clone()),
+        );
+
+        state
+    }
+
+    pub fn accounts(&self) -> &HashMap<String, Account> {
+        &self.accounts
+    }
+
+    pub fn processed_tx_state(&self, account_id: &str, tx_id: &str) -> Option<&AccountState> {
+        self.accounts.get(account_id)?.processed_tx_state(tx_id)
+    }
+
+    pub fn fx_rates(&self) -> &FxRates {
+        &self.fx_rates
+    }
+
+    pub fn has_shadow_rules(&self) -> bool {
+        !self.shadow_rules.is_empty()
+    }
+
+    /// Violations reported by the shadow rules for every transaction registered so far.
+    pub fn shadow_summary(&self) -> &ShadowSummary {
+        &self.shadow_summary
+    }
+
+    pub fn update_card_status(&mut self, account_id: &str, active_card: bool) -> AccountState {
+        let account = match self.accounts.get_mut(account_id) {
+            Some(account) => account,
+            None => return AccountState::not_initialized().with_account_id(account_id),
+        };
+
+        account.active_card = active_card;
+
+        account.to_state().with_account_id(account_id)
+    }
+
+    /// Replaces the merchant categories blocked for the account, applying to later transactions
+    /// and holds.
+    pub fn update_blocked_categories(
+        &mut self,
+        account_id: &str,
+        blocked_categories: Vec<MerchantCategory>,
+    ) -> AccountState {
+        let account = match self.accounts.get_mut(account_id) {
+            Some(account) => account,
+            None => return AccountState::not_initialized().with_account_id(account_id),
+        };
+
+        account.blocked_categories = blocked_categories;
+
+        account.to_state().with_account_id(account_id)
+    }
+
+    pub fn update_credit_limit(
+        &mut self,
+        account_id: &str,
+        total_limit: impl Into<Money>,
+    ) -> AccountState {
+        let account = match self.accounts.get_mut(account_id) {
+            Some(account) => account,
+            None => return AccountState::not_initialized().with_account_id(account_id),
+        };
+
+        let state = match account.update_credit_limit(total_limit) {
+            Ok(account_state) => account_state,
+            Err(error) => account.to_invalid_state(vec![error]),
+        };
+
+        state.with_account_id(account_id)
+    }
+
+    pub fn refund_tx(&mut self, account_id: &str, tx_id: &str) -> AccountState {
+        let account = match self.accounts.get_mut(account_id) {
+            Some(account) => account,
+            None => return AccountState::not_initialized().with_account_id(account_id),
+        };
+
+        let state = match account.refund_tx(tx_id) {
+            Ok(account_state) => account_state,
+            Err(error) => account.to_invalid_state(vec![error]),
+        };
+
+        state.with_account_id(account_id)
+    }
+
+    pub fn place_hold(&mut self, account_id: &str, hold: Hold) -> AccountState {
+        let account = match self.accounts.get_mut(account_id) {
+            Some(account) => account,
+            None => return AccountState::not_initialized().with_account_id(account_id),
+        };
+
+        account.expire_holds(hold.time, self.config.holds.expiry_seconds);
+
+        if account.is_inactive() {
+            return AccountState::inactive(account.available_limit).with_account_id(account_id);
+        }
+
+        let state = match account.place_hold(hold) {
+            Ok(account_state) => account_state,
+            Err(errors) => account.to_invalid_state(errors),
+        };
+
+        state.with_account_id(account_id)
+    }
+
+    /// Reserves a hold placed before a restart, without checking the rules again.
+    pub fn replay_hold(&mut self, account_id: &str, hold: Hold) -> AccountState {
+        let account = match self.accounts.get_mut(account_id) {
+            Some(account) => account,
+            None => return AccountState::not_initialized().with_account_id(account_id),
+        };
+
+        account.expire_holds(hold.time, self.config.holds.expiry_seconds);
+
+        let state = match account.apply_hold(hold) {
+            Ok(account_state) => account_state,
+            Err(error) => account.to_invalid_state(vec![error]),
+        };
+
+        state.with_account_id(account_id)
+    }
+
+    pub fn capture_hold(
+        &mut self,
+        account_id: &str,
+        hold_id: &str,
+        amount: impl Into<Money>,
+        time: DateTime<Utc>,
+    ) -> AccountState {
+        let account = match self.accounts.get_mut(account_id) {
+            Some(account) => account,
+            None => return AccountState::not_initialized().with_account_id(account_id),
+        };
+
+        account.expire_holds(time, self.config.holds.expiry_seconds);
+
+        let state = match account.capture_hold(hold_id, amount, time) {
+            Ok(account_state) => account_state,
+            Err(error) => account.to_invalid_state(vec![error]),
+        };
+
+        state.with_account_id(account_id)
+    }
+
+    pub fn release_hold(
+        &mut self,
+        account_id: &str,
+        hold_id: &str,
+        time: DateTime<Utc>,
+    ) -> AccountState {
+        let account = match self.accounts.get_mut(account_id) {
+            Some(account) => account,
+            None => return AccountState::not_initialized().with_account_id(account_id),
+        };
+
+        account.expire_holds(time, self.config.holds.expiry_seconds);
+
+        let state = match account.release_hold(hold_id) {
+            Ok(account_state) => account_state,
+            Err(error) => account.to_invalid_state(vec![error]),
+        };
+
+        state.with_account_id(account_id)
+    }
+
+    /// State for an operation rejected before it reaches the account, like one with an amount
+    /// that is invalid for its currency.
+    pub fn reject(&self, account_id: &str, errors: Vec<OperationError>) -> AccountState {
+        let state = match self.accounts.get(account_id) {
+            Some(account) => account.to_invalid_state(errors),
+            None => AccountState::new(
+                false,
+                0,
+                std::iter::once(OperationError::AccountNotInitialized)
+                    .chain(errors)
+                    .collect(),
+            ),
+        };
+
+        state.with_account_id(account_id)
     }
 
-    pub fn create_account(&mut self, account: Account) -> AccountState {
-        if self.account.is_some() {
-            return account.to_invalid_state(vec![OperationError::AccountAlreadyInitialized]);
+    /// State for an account creation rejected before the account is built, which reports no
+    /// missing account.
+    pub fn reject_account(&self, account_id: &str, errors: Vec<OperationError>) -> AccountState {
+        let state = match self.accounts.get(account_id) {
+            Some(account) => account.to_invalid_state(errors),
+            None => AccountState::new(false, Money::zero(Currency::default()), errors),
+        };
+
+        state.with_account_id(account_id)
+    }
+
+    pub fn account_state(&self, account_id: &str) -> Option<AccountState> {
+        self.accounts
+            .get(account_id)
+            .map(|account| account.to_state().with_account_id(account_id))
+    }
+
+    /// Loads a previously exported account with its own rules configuration, replacing any account
+    /// with the same id. Custom and shadow rules are not exported, so the registered ones apply.
+    pub fn restore_account(&mut self, account_id: String, account: Account) {
+        self.accounts.insert(
+            account_id,
+            account
+                .with_custom_rules(self.custom_rules.clone())
+                .with_shadow_rules(self.shadow_rules.clone()),
+        );
+    }
+
+    /// Applies a transaction authorized before a restart, without checking the rules again or
+    /// counting it in the shadow summary. A foreign-currency transaction takes the
+    /// `converted_amount` it was authorized for instead of being converted again.
+    pub fn replay_tx(
+        &mut self,
+        account_id: &str,
+        mut tx: TX,
+        converted_amount: Option<Money>,
+    ) -> AccountState {
+        let account = match self.accounts.get_mut(account_id) {
+            Some(account) => account,
+            None => return AccountState::not_initialized().with_account_id(account_id),
+        };
+
+        if let Some(converted_amount) = converted_amount {
+            tx.amount = converted_amount;
         }
 
-        let state = account.to_state();
+        account.expire_holds(tx.time, self.config.holds.expiry_seconds);
 
-        self.account = Option::Some(account);
+        let tx_id = tx.id.clone();
 
-        return state;
+        let mut state = match account.apply_tx(tx) {
+            Ok(account_state) => account_state,
+            Err(error) => account.to_invalid_state(vec![error]),
+        };
+
+        if let Some(converted_amount) = converted_amount {
+            state = state.with_converted_amount(converted_amount);
+        }
+
+        if let Some(tx_id) = tx_id {
+            account.record_processed_tx(tx_id, state.clone());
+        }
+
+        state.with_account_id(account_id)
     }
 
-    pub fn register_tx(&mut self, tx: TX) -> AccountState {
-        if self.account.is_none() {
-            return AccountState::not_initialized();
+    /// Records the result a transaction with `tx_id` was rejected with before a restart, so
+    /// resubmitting it returns that result instead of authorizing it again.
+    pub fn replay_rejected_tx(
+        &mut self,
+        account_id: &str,
+        tx_id: String,
+        state: AccountState,
+    ) -> AccountState {
+        let account = match self.accounts.get_mut(account_id) {
+            Some(account) => account,
+            None => return AccountState::not_initialized().with_account_id(account_id),
+        };
+
+        account.record_processed_tx(tx_id, state);
+
+        account.to_state().with_account_id(account_id)
+    }
+
+    pub fn register_tx(&mut self, account_id: &str, mut tx: TX) -> AccountState {
+        let account = match self.accounts.get_mut(account_id) {
+            Some(account) => account,
+            None => return AccountState::not_initialized().with_account_id(account_id),
+        };
+
+        if let Some(state) = tx
+            .id
+            .as_deref()
+            .and_then(|id| account.processed_tx_state(id))
+        {
+            return state.clone().with_account_id(account_id);
         }
 
-        let account = self.account.as_mut().unwrap();
+        let tx_id = tx.id.clone();
 
-        if account.is_inactive() {
-            return AccountState::inactive(account.available_limit);
+        // Transactions without a rate to the account currency are left for the account to reject.
+        let converted_amount = if account.ensure_currency(&tx.amount).is_err() {
+            self.fx_rates.convert(tx.amount, account.currency())
+        } else {
+            None
+        };
+
+        if let Some(converted_amount) = converted_amount {
+            tx.amount = converted_amount;
         }
 
-        let result = account.execute_tx(tx);
+        account.expire_holds(tx.time, self.config.holds.expiry_seconds);
+
+        let (result, trace) = account.execute_traced_tx(tx, self.explain);
 
-        match result {
+        let mut state = match result {
             Ok(account_state) => account_state,
-            Err(errors) => AccountState::new(account.active_card, account.available_limit, errors),
+            Err(violations) => account.to_invalid_state(violations),
+        };
+
+        let shadow_violations = shadow_violations(&trace);
+
+        if let Some(converted_amount) = converted_amount {
+            state = state.with_converted_amount(converted_amount);
+        }
+
+        if let Some(shadow_violations) = shadow_violations {
+            self.shadow_summary
+                .record(&shadow_violations, state.violations().is_empty());
+            state = state.with_shadow_violations(shadow_violations);
+        }
+
+        if self.explain {
+            state = state.with_trace(trace);
         }
+
+        if let Some(tx_id) = tx_id {
+            account.record_processed_tx(tx_id, state.clone());
+        }
+
+        state.with_account_id(account_id)
+    }
+}
+
+/// Violations the shadow rules in `trace` reported, if there were shadow rules and they ran.
+fn shadow_violations(trace: &[RuleTrace]) -> Option<Vec<Violation>> {
+    let mut shadow_trace = trace.iter().filter(|t| t.is_shadow()).peekable();
+
+    if shadow_trace.peek().is_none_or(|t| !t.ran()) {
+        return None;
     }
+
+    Some(
+        shadow_trace
+            .filter_map(|t| t.violation().cloned())
+            .collect(),
+    )
 }
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod authorizer {
 
-    use chrono::DateTime;
-
     use super::*;
 
     use pretty_assertions::assert_eq;
 
+    use crate::account::{RuleTrace, RulesConfig, Violation};
+
     #[test]
     fn create_account() {
         let mut authorizer = Authorizer::new();
 
-        let state = authorizer.create_account(Account::new(1000, true, vec![]));
+        let state = authorizer.create_account(String::new(), Account::new(1000, true, vec![]));
 
         let expected_state = AccountState::new(true, 1000, vec![]);
 
@@ -69,10 +475,16 @@ mod authorizer {
     #[test]
     fn create_duplicated_account() {
         let mut authorizer = Authorizer {
-            account: Some(Account::new(1000, true, vec![])),
+            accounts: HashMap::from([(String::new(), Account::new(1000, true, vec![]))]),
+            config: Config::default(),
+            fx_rates: FxRates::default(),
+            custom_rules: vec![],
+            shadow_rules: vec![],
+            shadow_summary: ShadowSummary::default(),
+            explain: false,
         };
 
-        let state = authorizer.create_account(Account::new(1000, true, vec![]));
+        let state = authorizer.create_account(String::new(), Account::new(1000, true, vec![]));
 
         let expected_state =
             AccountState::new(true, 1000, vec![OperationError::AccountAlreadyInitialized]);
@@ -84,23 +496,638 @@ mod authorizer {
     fn execute_tx_on_uninitialized_account() {
         let mut authorizer = Authorizer::new();
 
-        let state = authorizer.register_tx(TX::new(500, "Merchant X", DateTime::default()));
+        let state = authorizer.register_tx("", TX::new(500, "Merchant X", DateTime::default()));
 
         let expected_state = AccountState::not_initialized();
 
         assert_eq!(state, expected_state);
     }
 
+    #[test]
+    fn reject_operation_on_uninitialized_account() {
+        let authorizer = Authorizer::new();
+
+        let state = authorizer.reject("a", vec![OperationError::InvalidAmount]);
+
+        let expected_state = AccountState::new(
+            false,
+            0,
+            vec![
+                OperationError::AccountNotInitialized,
+                OperationError::InvalidAmount,
+            ],
+        )
+        .with_account_id("a");
+
+        assert_eq!(state, expected_state);
+    }
+
+    #[test]
+    fn reject_account_creation() {
+        let authorizer = Authorizer::new();
+
+        let state = authorizer.reject_account("a", vec![OperationError::InvalidAmount]);
+
+        let expected_state =
+            AccountState::new(false, 0, vec![OperationError::InvalidAmount]).with_account_id("a");
+
+        assert_eq!(state, expected_state);
+    }
+
     #[test]
     fn execute_tx_on_inactive_account() {
         let mut authorizer = Authorizer {
-            account: Some(Account::new(1000, false, vec![])),
+            accounts: HashMap::from([(String::new(), Account::new(1000, false, vec![]))]),
+            config: Config::default(),
+            fx_rates: FxRates::default(),
+            custom_rules: vec![],
+            shadow_rules: vec![],
+            shadow_summary: ShadowSummary::default(),
+            explain: false,
         };
 
-        let state = authorizer.register_tx(TX::new(500, "Merchant X", DateTime::default()));
+        let state = authorizer.register_tx("", TX::new(500, "Merchant X", DateTime::default()));
 
         let expected_state = AccountState::inactive(1000);
 
         assert_eq!(state, expected_state);
     }
+
+    #[test]
+    fn execute_tx_with_configured_rules() {
+        let mut config = Config::default();
+        config.rules.insufficient_limit.enabled = false;
+
+        let mut authorizer = Authorizer::with_config(config);
+        authorizer.create_account(String::new(), Account::new(100, true, vec![]));
+
+        let state = authorizer.register_tx("", TX::new(50, "Merchant X", DateTime::default()));
+        assert_eq!(state, AccountState::new(true, 50, vec![]));
+
+        let state = authorizer.register_tx("", TX::new(50, "Merchant X", DateTime::default()));
+        assert_eq!(
+            state,
+            AccountState::new(true, 50, vec![OperationError::DuplicatedTx])
+        );
+    }
+
+    #[test]
+    fn manage_accounts_by_id() {
+        let mut authorizer = Authorizer::new();
+
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+        authorizer.create_account(String::from("b"), Account::new(50, true, vec![]));
+
+        let state = authorizer.register_tx("a", TX::new(30, "Merchant X", DateTime::default()));
+        assert_eq!(
+            state,
+            AccountState::new(true, 70, vec![]).with_account_id("a")
+        );
+
+        let state = authorizer.register_tx("b", TX::new(30, "Merchant X", DateTime::default()));
+        assert_eq!(
+            state,
+            AccountState::new(true, 20, vec![]).with_account_id("b")
+        );
+    }
+
+    #[test]
+    fn create_duplicated_account_by_id() {
+        let mut authorizer = Authorizer::new();
+
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+        authorizer.create_account(String::from("b"), Account::new(50, true, vec![]));
+
+        let state = authorizer.create_account(String::from("a"), Account::new(500, true, vec![]));
+
+        let expected_state =
+            AccountState::new(true, 500, vec![OperationError::AccountAlreadyInitialized])
+                .with_account_id("a");
+
+        assert_eq!(state, expected_state);
+    }
+
+    #[test]
+    fn execute_tx_on_uninitialized_account_id() {
+        let mut authorizer = Authorizer::new();
+
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+
+        let state = authorizer.register_tx("b", TX::new(30, "Merchant X", DateTime::default()));
+
+        assert_eq!(state, AccountState::not_initialized().with_account_id("b"));
+    }
+
+    #[test]
+    fn restore_account_with_its_own_rules() {
+        let mut rules_config = RulesConfig::default();
+        rules_config.duplicated_tx.enabled = false;
+
+        let mut authorizer = Authorizer::new();
+        authorizer.restore_account(
+            String::from("a"),
+            Account::new(
+                100,
+                true,
+                vec![TX::new(20, "Merchant X", DateTime::default())],
+            )
+            .with_rules_config(rules_config),
+        );
+
+        let state = authorizer.register_tx("a", TX::new(20, "Merchant X", DateTime::default()));
+
+        assert_eq!(
+            state,
+            AccountState::new(true, 80, vec![]).with_account_id("a")
+        );
+    }
+
+    #[test]
+    fn get_account_state() {
+        let mut authorizer = Authorizer::new();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+
+        assert_eq!(
+            authorizer.account_state("a"),
+            Some(AccountState::new(true, 100, vec![]).with_account_id("a"))
+        );
+        assert_eq!(authorizer.account_state("b"), None);
+    }
+
+    #[test]
+    fn deactivate_and_reactivate_card() {
+        let mut authorizer = Authorizer::new();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+
+        let state = authorizer.update_card_status("a", false);
+        assert_eq!(
+            state,
+            AccountState::new(false, 100, vec![]).with_account_id("a")
+        );
+
+        let state = authorizer.register_tx("a", TX::new(20, "Merchant X", DateTime::default()));
+        assert_eq!(state, AccountState::inactive(100).with_account_id("a"));
+
+        let state = authorizer.update_card_status("a", true);
+        assert_eq!(
+            state,
+            AccountState::new(true, 100, vec![]).with_account_id("a")
+        );
+
+        let state = authorizer.register_tx("a", TX::new(20, "Merchant X", DateTime::default()));
+        assert_eq!(
+            state,
+            AccountState::new(true, 80, vec![]).with_account_id("a")
+        );
+    }
+
+    #[test]
+    fn update_card_status_on_uninitialized_account() {
+        let mut authorizer = Authorizer::new();
+
+        let state = authorizer.update_card_status("a", false);
+
+        assert_eq!(state, AccountState::not_initialized().with_account_id("a"));
+    }
+
+    #[test]
+    fn update_credit_limit() {
+        let mut authorizer = Authorizer::new();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+        authorizer.register_tx("a", TX::new(60, "Merchant X", DateTime::default()));
+
+        let state = authorizer.update_credit_limit("a", 150);
+        assert_eq!(
+            state,
+            AccountState::new(true, 90, vec![]).with_account_id("a")
+        );
+
+        let state = authorizer.update_credit_limit("a", 50);
+        assert_eq!(
+            state,
+            AccountState::new(true, 90, vec![OperationError::LimitBelowConsumed])
+                .with_account_id("a")
+        );
+
+        let state = authorizer.update_credit_limit("b", 50);
+        assert_eq!(state, AccountState::not_initialized().with_account_id("b"));
+    }
+
+    #[test]
+    fn refund_tx() {
+        let mut authorizer = Authorizer::new();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+        authorizer.register_tx(
+            "a",
+            TX::new(60, "Merchant X", DateTime::default()).with_id(Some(String::from("tx-1"))),
+        );
+
+        let state = authorizer.refund_tx("a", "tx-1");
+        assert_eq!(
+            state,
+            AccountState::new(true, 100, vec![]).with_account_id("a")
+        );
+
+        let state = authorizer.refund_tx("a", "tx-1");
+        assert_eq!(
+            state,
+            AccountState::new(true, 100, vec![OperationError::TransactionAlreadyReversed])
+                .with_account_id("a")
+        );
+
+        let state = authorizer.refund_tx("b", "tx-1");
+        assert_eq!(state, AccountState::not_initialized().with_account_id("b"));
+    }
+
+    #[test]
+    fn resubmit_authorized_tx() {
+        let mut authorizer = Authorizer::new();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+
+        let tx =
+            || TX::new(60, "Merchant X", DateTime::default()).with_id(Some(String::from("tx-1")));
+
+        let state = authorizer.register_tx("a", tx());
+        let resubmitted_state = authorizer.register_tx("a", tx());
+
+        assert_eq!(
+            state,
+            AccountState::new(true, 40, vec![]).with_account_id("a")
+        );
+        assert_eq!(resubmitted_state, state);
+        assert_eq!(authorizer.accounts()["a"].txs.len(), 1);
+    }
+
+    #[test]
+    fn resubmit_rejected_tx() {
+        let mut authorizer = Authorizer::new();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+
+        let state = authorizer.register_tx(
+            "a",
+            TX::new(160, "Merchant X", DateTime::default()).with_id(Some(String::from("tx-1"))),
+        );
+        authorizer.update_credit_limit("a", 200);
+        let resubmitted_state = authorizer.register_tx(
+            "a",
+            TX::new(160, "Merchant X", DateTime::default()).with_id(Some(String::from("tx-1"))),
+        );
+
+        assert_eq!(
+            state,
+            AccountState::new(true, 100, vec![OperationError::InsufficientLimit])
+                .with_account_id("a")
+        );
+        assert_eq!(resubmitted_state, state);
+    }
+
+    #[test]
+    fn repeat_tx_without_id() {
+        let mut authorizer = Authorizer::new();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+
+        authorizer.register_tx("a", TX::new(20, "Merchant X", DateTime::default()));
+        let state = authorizer.register_tx("a", TX::new(20, "Merchant X", DateTime::default()));
+
+        assert_eq!(
+            state,
+            AccountState::new(true, 80, vec![OperationError::DuplicatedTx]).with_account_id("a")
+        );
+    }
+
+    #[test]
+    fn hold_and_capture() {
+        let mut authorizer = Authorizer::new();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+
+        let state = authorizer.place_hold("a", Hold::new("h-1", 80, "Hotel", DateTime::default()));
+        assert_eq!(
+            state,
+            AccountState::new(true, 20, vec![]).with_account_id("a")
+        );
+
+        let state = authorizer.capture_hold("a", "h-1", 70, DateTime::default());
+        assert_eq!(
+            state,
+            AccountState::new(true, 30, vec![]).with_account_id("a")
+        );
+
+        let state = authorizer.release_hold("a", "h-1", DateTime::default());
+        assert_eq!(
+            state,
+            AccountState::new(true, 30, vec![OperationError::HoldAlreadySettled])
+                .with_account_id("a")
+        );
+    }
+
+    #[test]
+    fn update_blocked_categories() {
+        let mut authorizer = Authorizer::new();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+
+        let state = authorizer.update_blocked_categories("a", vec![MerchantCategory::Gambling]);
+        let blocked = authorizer.register_tx(
+            "a",
+            TX::new(10, "Casino", DateTime::default()).with_mcc(Some(7995)),
+        );
+        let not_initialized = authorizer.update_blocked_categories("b", vec![]);
+
+        assert_eq!(
+            state,
+            AccountState::new(true, 100, vec![]).with_account_id("a")
+        );
+        assert_eq!(
+            blocked,
+            AccountState::new(true, 100, vec![OperationError::BlockedMerchantCategory])
+                .with_account_id("a")
+        );
+        assert_eq!(
+            not_initialized,
+            AccountState::not_initialized().with_account_id("b")
+        );
+    }
+
+    #[test]
+    fn place_hold_on_inactive_account() {
+        let mut authorizer = Authorizer::new();
+        authorizer.create_account(String::from("a"), Account::new(100, false, vec![]));
+
+        let state = authorizer.place_hold("a", Hold::new("h-1", 80, "Hotel", DateTime::default()));
+
+        assert_eq!(state, AccountState::inactive(100).with_account_id("a"));
+    }
+
+    #[test]
+    fn expire_holds_on_later_events() {
+        let mut config = Config::default();
+        config.holds.expiry_seconds = 3600;
+
+        let mut authorizer = Authorizer::with_config(config);
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+        authorizer.place_hold(
+            "a",
+            Hold::new(
+                "h-1",
+                80,
+                "Fuel",
+                DateTime::parse_from_rfc3339("2019-02-13T10:00:00.000Z")
+                    .unwrap()
+                    .into(),
+            ),
+        );
+
+        let state = authorizer.register_tx(
+            "a",
+            TX::new(
+                50,
+                "Merchant X",
+                DateTime::parse_from_rfc3339("2019-02-13T11:00:01.000Z")
+                    .unwrap()
+                    .into(),
+            ),
+        );
+        assert_eq!(
+            state,
+            AccountState::new(true, 50, vec![]).with_account_id("a")
+        );
+
+        let state = authorizer.capture_hold(
+            "a",
+            "h-1",
+            80,
+            DateTime::parse_from_rfc3339("2019-02-13T11:00:02.000Z")
+                .unwrap()
+                .into(),
+        );
+        assert_eq!(
+            state,
+            AccountState::new(true, 50, vec![OperationError::HoldExpired]).with_account_id("a")
+        );
+    }
+
+    #[test]
+    fn register_foreign_currency_tx() {
+        let fx_rates =
+            serde_json::from_str("{\"markup-percent\": 2.5, \"rates\": {\"EUR/USD\": 1.0841}}")
+                .unwrap();
+        let mut authorizer = Authorizer::builder().fx_rates(fx_rates).build();
+        let eur = Currency::from_code("EUR").unwrap();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+
+        let converted = authorizer.register_tx(
+            "a",
+            TX::new(Money::new(2000, eur), "Merchant X", DateTime::default()),
+        );
+        let insufficient_limit = authorizer.register_tx(
+            "a",
+            TX::new(Money::new(8000, eur), "Merchant Y", DateTime::default()),
+        );
+        let without_rate = authorizer.register_tx(
+            "a",
+            TX::new(
+                Money::new(1000, Currency::from_code("GBP").unwrap()),
+                "Merchant Z",
+                DateTime::default(),
+            ),
+        );
+
+        assert_eq!(
+            converted,
+            AccountState::new(true, Money::new(7778, Currency::USD), vec![])
+                .with_converted_amount(Money::new(2222, Currency::USD))
+                .with_account_id("a")
+        );
+        assert_eq!(
+            insufficient_limit,
+            AccountState::new(
+                true,
+                Money::new(7778, Currency::USD),
+                vec![OperationError::InsufficientLimit]
+            )
+            .with_converted_amount(Money::new(8890, Currency::USD))
+            .with_account_id("a")
+        );
+        assert_eq!(
+            without_rate,
+            AccountState::new(
+                true,
+                Money::new(7778, Currency::USD),
+                vec![OperationError::CurrencyMismatch]
+            )
+            .with_account_id("a")
+        );
+    }
+
+    #[derive(Debug)]
+    struct BlockedMerchant(&'static str);
+
+    impl AccountRule for BlockedMerchant {
+        fn validate(&self, _account: &Account, tx: &TX) -> Option<Violation> {
+            if tx.merchant == self.0 {
+                return Some(
+                    Violation::new("blocked-merchant").with_detail(serde_json::json!(self.0)),
+                );
+            }
+
+            None
+        }
+    }
+
+    #[test]
+    fn build_with_custom_rules() {
+        let mut rules_config = RulesConfig::default();
+        rules_config.insufficient_limit.enabled = false;
+
+        let mut authorizer = Authorizer::builder()
+            .config(Config {
+                rules: rules_config,
+                ..Config::default()
+            })
+            .rule(BlockedMerchant("Casino"))
+            .build();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+
+        let blocked = authorizer.register_tx("a", TX::new(10, "Casino", DateTime::default()));
+        let authorized =
+            authorizer.register_tx("a", TX::new(150, "Merchant X", DateTime::default()));
+
+        assert_eq!(
+            blocked,
+            AccountState::from_violations(
+                true,
+                100,
+                vec![Violation::new("blocked-merchant").with_detail(serde_json::json!("Casino"))]
+            )
+            .with_account_id("a")
+        );
+        assert_eq!(
+            authorized,
+            AccountState::new(true, 0, vec![]).with_account_id("a")
+        );
+    }
+
+    #[test]
+    fn apply_custom_rules_to_restored_accounts() {
+        let mut authorizer = Authorizer::builder()
+            .rule(BlockedMerchant("Casino"))
+            .build();
+        authorizer.restore_account(String::from("a"), Account::new(100, true, vec![]));
+
+        let state = authorizer.register_tx("a", TX::new(10, "Casino", DateTime::default()));
+
+        assert_eq!(
+            state,
+            AccountState::from_violations(
+                true,
+                100,
+                vec![Violation::new("blocked-merchant").with_detail(serde_json::json!("Casino"))]
+            )
+            .with_account_id("a")
+        );
+    }
+
+    #[test]
+    fn register_tx_with_explain() {
+        let mut rules_config = RulesConfig::default();
+        rules_config.high_frequency_small_interval.enabled = false;
+        rules_config.duplicated_tx.enabled = false;
+        rules_config.blocked_merchant_category.enabled = false;
+        rules_config.daily_limit.enabled = false;
+        rules_config.monthly_limit.enabled = false;
+
+        let mut authorizer = Authorizer::builder()
+            .config(Config {
+                rules: rules_config,
+                ..Config::default()
+            })
+            .rule(BlockedMerchant("Casino"))
+            .explain(true)
+            .build();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+
+        let state = authorizer.register_tx("a", TX::new(150, "Casino", DateTime::default()));
+
+        assert_eq!(
+            state.trace(),
+            [
+                RuleTrace::new(
+                    "insufficient-limit",
+                    Some(OperationError::InsufficientLimit.into())
+                )
+                .with_evidence(serde_json::json!({"available-limit": "100", "amount": "150"})),
+                RuleTrace::new(
+                    "BlockedMerchant",
+                    Some(
+                        Violation::new("blocked-merchant").with_detail(serde_json::json!("Casino"))
+                    )
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn register_tx_without_explain() {
+        let mut authorizer = Authorizer::new();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+
+        let state = authorizer.register_tx("a", TX::new(10, "Nike", DateTime::default()));
+
+        assert!(state.trace().is_empty());
+    }
+
+    #[test]
+    fn register_tx_with_shadow_rules() {
+        let mut authorizer = Authorizer::builder()
+            .shadow_rule(BlockedMerchant("Casino"))
+            .build();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+
+        let authorized = authorizer.register_tx("a", TX::new(10, "Casino", DateTime::default()));
+        let declined = authorizer.register_tx("a", TX::new(150, "Casino", DateTime::default()));
+        authorizer.register_tx("a", TX::new(20, "Nike", DateTime::default()));
+
+        let shadow_violation =
+            Violation::new("blocked-merchant").with_detail(serde_json::json!("Casino"));
+
+        assert_eq!(
+            authorized,
+            AccountState::new(true, 90, vec![])
+                .with_shadow_violations(vec![shadow_violation.clone()])
+                .with_account_id("a")
+        );
+        assert_eq!(
+            declined,
+            AccountState::new(true, 90, vec![OperationError::InsufficientLimit])
+                .with_shadow_violations(vec![shadow_violation])
+                .with_account_id("a")
+        );
+
+        let summary = authorizer.shadow_summary();
+
+        assert_eq!(summary.checked_txs(), 3);
+        assert_eq!(summary.flagged_txs(), 2);
+        assert_eq!(summary.would_decline(), 1);
+        assert_eq!(
+            summary.violations(),
+            &std::collections::BTreeMap::from([(String::from("blocked-merchant"), 2)])
+        );
+    }
+
+    #[test]
+    fn explain_shadow_rules() {
+        let mut authorizer = Authorizer::builder()
+            .shadow_rule(BlockedMerchant("Casino"))
+            .explain(true)
+            .build();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+
+        let state = authorizer.register_tx("a", TX::new(10, "Casino", DateTime::default()));
+
+        let shadow_trace = state.trace().last().unwrap();
+
+        assert_eq!(shadow_trace.rule(), "BlockedMerchant");
+        assert!(shadow_trace.is_shadow());
+        assert!(shadow_trace.violation().is_some());
+        assert!(state.violations().is_empty());
+    }
 }
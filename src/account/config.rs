@@ -0,0 +1,276 @@
+use chrono_tz::Tz;
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::account::{
+    AccountRule, BlockedMerchantCategory, DailyLimit, DuplicatedTx, HighFrequencySmallInterval,
+    InsufficientLimit, MerchantLimit, Money, MonthlyLimit,
+};
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct Config {
+    pub rules: RulesConfig,
+    pub holds: HoldsConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct HoldsConfig {
+    /// Seconds, in event time, after which an uncaptured hold is released.
+    pub expiry_seconds: i64,
+}
+
+impl Default for HoldsConfig {
+    fn default() -> Self {
+        Self {
+            expiry_seconds: 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct RulesConfig {
+    pub insufficient_limit: InsufficientLimitConfig,
+    pub high_frequency_small_interval: HighFrequencySmallIntervalConfig,
+    pub duplicated_tx: DuplicatedTxConfig,
+    pub blocked_merchant_category: BlockedMerchantCategoryConfig,
+    pub daily_limit: SpendingCapConfig,
+    pub monthly_limit: SpendingCapConfig,
+    pub merchant_limit: MerchantLimitConfig,
+}
+
+impl RulesConfig {
+    pub fn rules(&self) -> Vec<Box<dyn AccountRule>> {
+        let mut rules: Vec<Box<dyn AccountRule>> = vec![];
+
+        if self.insufficient_limit.enabled {
+            rules.push(InsufficientLimit::boxed());
+        }
+
+        if self.high_frequency_small_interval.enabled {
+            rules.push(HighFrequencySmallInterval::boxed(
+                self.high_frequency_small_interval.max_txs,
+                self.high_frequency_small_interval.window_seconds,
+            ));
+        }
+
+        if self.duplicated_tx.enabled {
+            rules.push(DuplicatedTx::boxed(self.duplicated_tx.window_seconds));
+        }
+
+        if self.blocked_merchant_category.enabled {
+            rules.push(BlockedMerchantCategory::boxed());
+        }
+
+        if self.daily_limit.enabled {
+            rules.push(DailyLimit::boxed(self.daily_limit.timezone));
+        }
+
+        if self.monthly_limit.enabled {
+            rules.push(MonthlyLimit::boxed(self.monthly_limit.timezone));
+        }
+
+        let merchant_limit = &self.merchant_limit;
+
+        if merchant_limit.enabled
+            && (merchant_limit.max_amount.is_some() || merchant_limit.max_txs.is_some())
+        {
+            rules.push(MerchantLimit::boxed(
+                merchant_limit.window_seconds,
+                merchant_limit.max_amount,
+                merchant_limit.max_txs,
+            ));
+        }
+
+        rules
+    }
+}
+
+/// Rejects negative windows, which no transaction could ever fall in.
+fn non_negative_seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    let seconds = i64::deserialize(deserializer)?;
+
+    if seconds < 0 {
+        return Err(de::Error::custom(format!(
+            "window-seconds must not be negative, found {}",
+            seconds
+        )));
+    }
+
+    Ok(seconds)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct InsufficientLimitConfig {
+    pub enabled: bool,
+}
+
+impl Default for InsufficientLimitConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct HighFrequencySmallIntervalConfig {
+    pub enabled: bool,
+    pub max_txs: usize,
+    #[serde(deserialize_with = "non_negative_seconds")]
+    pub window_seconds: i64,
+}
+
+impl Default for HighFrequencySmallIntervalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_txs: HighFrequencySmallInterval::DEFAULT_MAX_TXS,
+            window_seconds: HighFrequencySmallInterval::DEFAULT_WINDOW_SECONDS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct DuplicatedTxConfig {
+    pub enabled: bool,
+    #[serde(deserialize_with = "non_negative_seconds")]
+    pub window_seconds: i64,
+}
+
+impl Default for DuplicatedTxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_seconds: DuplicatedTx::DEFAULT_WINDOW_SECONDS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct BlockedMerchantCategoryConfig {
+    pub enabled: bool,
+}
+
+impl Default for BlockedMerchantCategoryConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct SpendingCapConfig {
+    pub enabled: bool,
+    /// IANA timezone where the calendar days and months of the caps start.
+    pub timezone: Tz,
+}
+
+impl Default for SpendingCapConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timezone: Tz::UTC,
+        }
+    }
+}
+
+/// The merchant limit only applies once a `max-amount` or `max-txs` is set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct MerchantLimitConfig {
+    pub enabled: bool,
+    #[serde(deserialize_with = "non_negative_seconds")]
+    pub window_seconds: i64,
+    /// Amount that can be spent at a single merchant, only capped for accounts in its currency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_amount: Option<Money>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_txs: Option<usize>,
+}
+
+impl Default for MerchantLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_seconds: MerchantLimit::DEFAULT_WINDOW_SECONDS,
+            max_amount: None,
+            max_txs: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod rules_config {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn build_default_rules() {
+        let rules = RulesConfig::default().rules();
+
+        assert_eq!(
+            format!("{:?}", rules),
+            format!(
+                "{:?}",
+                vec![
+                    InsufficientLimit::boxed() as Box<dyn AccountRule>,
+                    HighFrequencySmallInterval::boxed(3, 120),
+                    DuplicatedTx::boxed(120),
+                    BlockedMerchantCategory::boxed(),
+                    DailyLimit::boxed(Tz::UTC),
+                    MonthlyLimit::boxed(Tz::UTC),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn build_configured_rules() {
+        let config = RulesConfig {
+            insufficient_limit: InsufficientLimitConfig { enabled: false },
+            high_frequency_small_interval: HighFrequencySmallIntervalConfig {
+                enabled: true,
+                max_txs: 5,
+                window_seconds: 60,
+            },
+            duplicated_tx: DuplicatedTxConfig {
+                enabled: false,
+                window_seconds: 120,
+            },
+            blocked_merchant_category: BlockedMerchantCategoryConfig { enabled: false },
+            daily_limit: SpendingCapConfig {
+                enabled: false,
+                timezone: Tz::UTC,
+            },
+            monthly_limit: SpendingCapConfig {
+                enabled: true,
+                timezone: Tz::America__Sao_Paulo,
+            },
+            merchant_limit: MerchantLimitConfig {
+                enabled: true,
+                window_seconds: 3600,
+                max_amount: None,
+                max_txs: Some(2),
+            },
+        };
+
+        let rules = config.rules();
+
+        assert_eq!(
+            format!("{:?}", rules),
+            format!(
+                "{:?}",
+                vec![
+                    HighFrequencySmallInterval::boxed(5, 60) as Box<dyn AccountRule>,
+                    MonthlyLimit::boxed(Tz::America__Sao_Paulo),
+                    MerchantLimit::boxed(3600, None, Some(2)),
+                ]
+            )
+        );
+    }
+}
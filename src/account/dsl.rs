@@ -0,0 +1,870 @@
+//! Rules written in a small text language, one per line:
+//!
+//! ```text
+//! count(tx where merchant == tx.merchant, within 10m) > 2 => "merchant-velocity"
+//! ```
+//!
+//! A rule is a condition on the transaction being authorized, its account and its authorized
+//! transactions, rejecting the transaction with the quoted violation code when it holds.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use thiserror::Error;
+
+use crate::account::{Account, AccountRule, Decimal, Violation, TX};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("line {line}, column {column}: {message}")]
+pub struct DslError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+/// Parses every rule in `source`, skipping blank lines and `#` comments.
+pub fn parse_rules(source: &str) -> Result<Vec<DslRule>, DslError> {
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .map(|(index, line)| {
+            DslRule::parse(line).map_err(|(column, message)| DslError {
+                line: index + 1,
+                column,
+                message,
+            })
+        })
+        .collect()
+}
+
+/// Rule compiled from its text form.
+#[derive(Debug)]
+pub struct DslRule {
+    source: String,
+    condition: Expr,
+    code: String,
+}
+
+impl DslRule {
+    /// Parses a single rule, returning the 1-based column and message of any error.
+    fn parse(source: &str) -> Result<Self, (usize, String)> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            in_aggregate: false,
+            end_column: source.chars().count() + 1,
+        };
+
+        let (condition, condition_type) = parser.expr()?;
+        parser.expect_type(condition_type, Type::Bool, "the rule condition")?;
+        parser.expect(&Token::Arrow, "'=>'")?;
+
+        let code = match parser.next() {
+            Some((_, Token::Text(code))) if !code.is_empty() => code,
+            other => return Err(parser.unexpected(other, "a quoted violation code")),
+        };
+
+        if let Some(token) = parser.next() {
+            return Err(parser.unexpected(Some(token), "the end of the rule"));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            condition,
+            code,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl AccountRule for DslRule {
+    fn validate(&self, account: &Account, tx: &TX) -> Option<Violation> {
+        let scope = Scope {
+            account,
+            tx,
+            item: None,
+        };
+
+        match scope.eval(&self.condition) {
+            Value::Bool(true) => Some(Violation::new(&self.code)),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.source
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(Decimal),
+    Duration(i64),
+    Text(String),
+    Compare(CompareOp),
+    Arrow,
+    Dot,
+    Comma,
+    LeftParen,
+    RightParen,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "'{}'", ident),
+            Token::Number(number) => write!(f, "'{}'", number),
+            Token::Duration(seconds) => write!(f, "'{}s'", seconds),
+            Token::Text(text) => write!(f, "{:?}", text),
+            Token::Compare(op) => write!(f, "'{}'", op.symbol()),
+            Token::Arrow => write!(f, "'=>'"),
+            Token::Dot => write!(f, "'.'"),
+            Token::Comma => write!(f, "','"),
+            Token::LeftParen => write!(f, "'('"),
+            Token::RightParen => write!(f, "')'"),
+        }
+    }
+}
+
+/// Decimal places a number literal can have, more than any currency amount it's compared with.
+const MAX_DECIMAL_PLACES: usize = 18;
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, (usize, String)> {
+    let mut tokens = vec![];
+    let mut chars: Peekable<CharIndices> = source.char_indices().peekable();
+    let column = |index: usize| source[..index].chars().count() + 1;
+
+    while let Some((index, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '.' => Token::Dot,
+            ',' => Token::Comma,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '=' | '!' | '<' | '>' => {
+                let next = chars.next_if(|&(_, n)| n == '=' || (c == '=' && n == '>'));
+
+                match (c, next.map(|(_, n)| n)) {
+                    ('=', Some('>')) => Token::Arrow,
+                    ('=', Some('=')) => Token::Compare(CompareOp::Eq),
+                    ('!', Some('=')) => Token::Compare(CompareOp::Ne),
+                    ('<', Some('=')) => Token::Compare(CompareOp::Le),
+                    ('>', Some('=')) => Token::Compare(CompareOp::Ge),
+                    ('<', None) => Token::Compare(CompareOp::Lt),
+                    ('>', None) => Token::Compare(CompareOp::Gt),
+                    _ => return Err((column(index), format!("unexpected '{}'", c))),
+                }
+            }
+            '"' => {
+                let mut text = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped)) => text.push(escaped),
+                            None => return Err((column(index), "unterminated text".to_string())),
+                        },
+                        Some((_, c)) => text.push(c),
+                        None => return Err((column(index), "unterminated text".to_string())),
+                    }
+                }
+
+                Token::Text(text)
+            }
+            c if c.is_ascii_digit() => {
+                let mut end = index + 1;
+                while let Some((i, _)) = chars.next_if(|&(_, n)| n.is_ascii_digit() || n == '.') {
+                    end = i + 1;
+                }
+                let number = &source[index..end];
+
+                match chars.next_if(|&(_, n)| n.is_ascii_alphabetic()) {
+                    Some((_, unit)) => {
+                        let seconds = match unit {
+                            's' => 1,
+                            'm' => 60,
+                            'h' => 60 * 60,
+                            'd' => 24 * 60 * 60,
+                            _ => {
+                                return Err((
+                                    column(index),
+                                    format!("unknown duration unit '{}'", unit),
+                                ))
+                            }
+                        };
+                        let invalid = || {
+                            (
+                                column(index),
+                                format!("invalid duration '{}{}'", number, unit),
+                            )
+                        };
+                        let amount: i64 = number.parse().map_err(|_| invalid())?;
+
+                        Token::Duration(amount.checked_mul(seconds).ok_or_else(invalid)?)
+                    }
+                    None => {
+                        let decimal_places = number.split_once('.').map_or(0, |(_, f)| f.len());
+
+                        if decimal_places > MAX_DECIMAL_PLACES {
+                            return Err((
+                                column(index),
+                                format!(
+                                    "number '{}' has more than {} decimal places",
+                                    number, MAX_DECIMAL_PLACES
+                                ),
+                            ));
+                        }
+
+                        Token::Number(number.parse().map_err(|e| (column(index), e))?)
+                    }
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some((_, n)) =
+                    chars.next_if(|&(_, n)| n.is_ascii_alphanumeric() || n == '_')
+                {
+                    ident.push(n);
+                }
+
+                Token::Ident(ident)
+            }
+            _ => return Err((column(index), format!("unexpected '{}'", c))),
+        };
+
+        tokens.push((column(index), token));
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+
+    fn matches(&self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Eq => ordering == Ordering::Equal,
+            CompareOp::Ne => ordering != Ordering::Equal,
+            CompareOp::Lt => ordering == Ordering::Less,
+            CompareOp::Le => ordering != Ordering::Greater,
+            CompareOp::Gt => ordering == Ordering::Greater,
+            CompareOp::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Type {
+    Number,
+    Text,
+    Bool,
+}
+
+impl Type {
+    fn name(&self) -> &'static str {
+        match self {
+            Type::Number => "a number",
+            Type::Text => "text",
+            Type::Bool => "a condition",
+        }
+    }
+}
+
+/// Transaction the field is read from: the one being authorized, or the authorized one an
+/// aggregate is going through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Subject {
+    Tx,
+    Item,
+    Account,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Merchant,
+    Amount,
+    Mcc,
+    Id,
+    AvailableLimit,
+    TotalLimit,
+    ActiveCard,
+}
+
+impl Field {
+    fn parse(subject: Subject, name: &str) -> Option<(Field, Type)> {
+        match (subject, name) {
+            (Subject::Tx | Subject::Item, "merchant") => Some((Field::Merchant, Type::Text)),
+            (Subject::Tx | Subject::Item, "amount") => Some((Field::Amount, Type::Number)),
+            (Subject::Tx | Subject::Item, "mcc") => Some((Field::Mcc, Type::Number)),
+            (Subject::Tx | Subject::Item, "id") => Some((Field::Id, Type::Text)),
+            (Subject::Account, "available_limit") => Some((Field::AvailableLimit, Type::Number)),
+            (Subject::Account, "total_limit") => Some((Field::TotalLimit, Type::Number)),
+            (Subject::Account, "active_card") => Some((Field::ActiveCard, Type::Bool)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Aggregate {
+    Count,
+    Sum,
+}
+
+#[derive(Debug)]
+enum Expr {
+    Literal(Value),
+    Field(Subject, Field),
+    Aggregate {
+        aggregate: Aggregate,
+        condition: Option<Box<Expr>>,
+        within_seconds: Option<i64>,
+    },
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    in_aggregate: bool,
+    end_column: usize,
+}
+
+type ParseResult<T> = Result<T, (usize, String)>;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.end_column, |(column, _)| *column)
+    }
+
+    fn next(&mut self) -> Option<(usize, Token)> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn next_if_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(ident)) if ident == keyword) {
+            self.position += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn unexpected(&self, token: Option<(usize, Token)>, expected: &str) -> (usize, String) {
+        match token {
+            Some((column, token)) => (column, format!("expected {}, found {}", expected, token)),
+            None => (self.end_column, format!("expected {}", expected)),
+        }
+    }
+
+    fn expect(&mut self, expected: &Token, description: &str) -> ParseResult<()> {
+        match self.next() {
+            Some((_, token)) if &token == expected => Ok(()),
+            other => Err(self.unexpected(other, description)),
+        }
+    }
+
+    fn expect_type(&self, actual: Type, expected: Type, what: &str) -> ParseResult<()> {
+        if actual != expected {
+            return Err((
+                self.column(),
+                format!(
+                    "{} must be {}, not {}",
+                    what,
+                    expected.name(),
+                    actual.name()
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn expr(&mut self) -> ParseResult<(Expr, Type)> {
+        let (mut left, mut left_type) = self.and()?;
+
+        while self.next_if_keyword("or") {
+            let (right, right_type) = self.and()?;
+            self.expect_type(left_type, Type::Bool, "'or' operands")?;
+            self.expect_type(right_type, Type::Bool, "'or' operands")?;
+
+            left = Expr::Or(Box::new(left), Box::new(right));
+            left_type = Type::Bool;
+        }
+
+        Ok((left, left_type))
+    }
+
+    fn and(&mut self) -> ParseResult<(Expr, Type)> {
+        let (mut left, mut left_type) = self.unary()?;
+
+        while self.next_if_keyword("and") {
+            let (right, right_type) = self.unary()?;
+            self.expect_type(left_type, Type::Bool, "'and' operands")?;
+            self.expect_type(right_type, Type::Bool, "'and' operands")?;
+
+            left = Expr::And(Box::new(left), Box::new(right));
+            left_type = Type::Bool;
+        }
+
+        Ok((left, left_type))
+    }
+
+    fn unary(&mut self) -> ParseResult<(Expr, Type)> {
+        if self.next_if_keyword("not") {
+            let (expr, expr_type) = self.unary()?;
+            self.expect_type(expr_type, Type::Bool, "'not' operand")?;
+
+            return Ok((Expr::Not(Box::new(expr)), Type::Bool));
+        }
+
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> ParseResult<(Expr, Type)> {
+        let (left, left_type) = self.operand()?;
+
+        let op = match self.peek() {
+            Some(Token::Compare(op)) => *op,
+            _ => return Ok((left, left_type)),
+        };
+        let column = self.column();
+        self.position += 1;
+
+        let (right, right_type) = self.operand()?;
+
+        if left_type != right_type {
+            return Err((
+                column,
+                format!(
+                    "can't compare {} with {}",
+                    left_type.name(),
+                    right_type.name()
+                ),
+            ));
+        }
+
+        if left_type != Type::Number && !matches!(op, CompareOp::Eq | CompareOp::Ne) {
+            return Err((
+                column,
+                format!("{} can only be compared for equality", left_type.name()),
+            ));
+        }
+
+        Ok((
+            Expr::Compare(Box::new(left), op, Box::new(right)),
+            Type::Bool,
+        ))
+    }
+
+    fn operand(&mut self) -> ParseResult<(Expr, Type)> {
+        let column = self.column();
+
+        match self.next() {
+            Some((_, Token::Number(number))) => {
+                Ok((Expr::Literal(Value::Number(number)), Type::Number))
+            }
+            Some((_, Token::Text(text))) => Ok((Expr::Literal(Value::Text(text)), Type::Text)),
+            Some((_, Token::LeftParen)) => {
+                let expr = self.expr()?;
+                self.expect(&Token::RightParen, "')'")?;
+                Ok(expr)
+            }
+            Some((_, Token::Ident(ident))) => match ident.as_str() {
+                "true" => Ok((Expr::Literal(Value::Bool(true)), Type::Bool)),
+                "false" => Ok((Expr::Literal(Value::Bool(false)), Type::Bool)),
+                "count" => self.aggregate(Aggregate::Count, column),
+                "sum" => self.aggregate(Aggregate::Sum, column),
+                "tx" | "account" => {
+                    self.expect(&Token::Dot, "'.'")?;
+
+                    let subject = if ident == "tx" {
+                        Subject::Tx
+                    } else {
+                        Subject::Account
+                    };
+
+                    match self.next() {
+                        Some((column, Token::Ident(name))) => self.field(subject, &name, column),
+                        other => Err(self.unexpected(other, "a field name")),
+                    }
+                }
+                name if self.in_aggregate => self.field(Subject::Item, name, column),
+                name => Err((
+                    column,
+                    format!(
+                        "unknown name '{}', fields of the transaction are written as tx.{}",
+                        name, name
+                    ),
+                )),
+            },
+            other => Err(self.unexpected(other, "a value")),
+        }
+    }
+
+    fn field(&self, subject: Subject, name: &str, column: usize) -> ParseResult<(Expr, Type)> {
+        Field::parse(subject, name)
+            .map(|(field, field_type)| (Expr::Field(subject, field), field_type))
+            .ok_or_else(|| (column, format!("unknown field '{}'", name)))
+    }
+
+    /// Parses `count(tx [where <condition>] [, within <duration>])`, and the same for `sum`.
+    fn aggregate(&mut self, aggregate: Aggregate, column: usize) -> ParseResult<(Expr, Type)> {
+        if self.in_aggregate {
+            return Err((column, "aggregates can't be nested".to_string()));
+        }
+
+        self.expect(&Token::LeftParen, "'('")?;
+
+        if !self.next_if_keyword("tx") {
+            let token = self.next();
+            return Err(self.unexpected(token, "'tx'"));
+        }
+
+        let condition = if self.next_if_keyword("where") {
+            self.in_aggregate = true;
+            let condition = self.expr();
+            self.in_aggregate = false;
+
+            let (condition, condition_type) = condition?;
+            self.expect_type(condition_type, Type::Bool, "the 'where' condition")?;
+
+            Some(Box::new(condition))
+        } else {
+            None
+        };
+
+        let within_seconds = if self.peek() == Some(&Token::Comma) {
+            self.position += 1;
+
+            if !self.next_if_keyword("within") {
+                let token = self.next();
+                return Err(self.unexpected(token, "'within'"));
+            }
+
+            match self.next() {
+                Some((_, Token::Duration(seconds))) => Some(seconds),
+                other => return Err(self.unexpected(other, "a duration like 10m")),
+            }
+        } else {
+            None
+        };
+
+        self.expect(&Token::RightParen, "')'")?;
+
+        Ok((
+            Expr::Aggregate {
+                aggregate,
+                condition,
+                within_seconds,
+            },
+            Type::Number,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(Decimal),
+    Text(String),
+    Bool(bool),
+    /// Optional field without a value, which is only different from every other value.
+    Missing,
+}
+
+struct Scope<'a> {
+    account: &'a Account,
+    tx: &'a TX,
+    item: Option<&'a TX>,
+}
+
+impl Scope<'_> {
+    fn eval(&self, expr: &Expr) -> Value {
+        match expr {
+            Expr::Literal(value) => value.clone(),
+            Expr::Field(subject, field) => self.field(*subject, *field),
+            Expr::Aggregate {
+                aggregate,
+                condition,
+                within_seconds,
+            } => self.aggregate(*aggregate, condition.as_deref(), *within_seconds),
+            Expr::Compare(left, op, right) => {
+                let ordering = match (self.eval(left), self.eval(right)) {
+                    (Value::Number(l), Value::Number(r)) => Some(l.cmp(&r)),
+                    (Value::Text(l), Value::Text(r)) => Some(l.cmp(&r)),
+                    (Value::Bool(l), Value::Bool(r)) => Some(l.cmp(&r)),
+                    _ => None,
+                };
+
+                Value::Bool(ordering.map_or(*op == CompareOp::Ne, |ordering| op.matches(ordering)))
+            }
+            Expr::And(left, right) => Value::Bool(self.is_true(left) && self.is_true(right)),
+            Expr::Or(left, right) => Value::Bool(self.is_true(left) || self.is_true(right)),
+            Expr::Not(expr) => Value::Bool(!self.is_true(expr)),
+        }
+    }
+
+    fn is_true(&self, expr: &Expr) -> bool {
+        self.eval(expr) == Value::Bool(true)
+    }
+
+    fn field(&self, subject: Subject, field: Field) -> Value {
+        let tx = match subject {
+            Subject::Item => self.item.unwrap_or(self.tx),
+            Subject::Tx | Subject::Account => self.tx,
+        };
+
+        match field {
+            Field::Merchant => Value::Text(tx.merchant.clone()),
+            Field::Amount => Value::Number(tx.amount.to_decimal()),
+            Field::Mcc => tx.mcc.map_or(Value::Missing, |mcc| {
+                Value::Number(Decimal::new(mcc.into(), 0))
+            }),
+            Field::Id => tx.id.clone().map_or(Value::Missing, Value::Text),
+            Field::AvailableLimit => Value::Number(self.account.available_limit.to_decimal()),
+            Field::TotalLimit => Value::Number(self.account.total_limit.to_decimal()),
+            Field::ActiveCard => Value::Bool(self.account.active_card),
+        }
+    }
+
+    /// Goes through the authorized transactions that weren't reversed, leaving out the one being
+    /// authorized.
+    fn aggregate(
+        &self,
+        aggregate: Aggregate,
+        condition: Option<&Expr>,
+        within_seconds: Option<i64>,
+    ) -> Value {
+        let matching = |t: &TX| {
+            let item_scope = Scope {
+                account: self.account,
+                tx: self.tx,
+                item: Some(t),
+            };
+
+            !t.reversed
+                && within_seconds.is_none_or(|w| self.tx.seconds_since(t).abs() <= w)
+                && condition.is_none_or(|c| item_scope.is_true(c))
+        };
+
+        match aggregate {
+            Aggregate::Count => {
+                let count = self.account.txs.iter().filter(|t| matching(t)).count();
+                Value::Number(Decimal::new(count as u64, 0))
+            }
+            Aggregate::Sum => Value::Number(self.account.spent_where(matching).to_decimal()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod dsl_rules {
+    use super::*;
+
+    use chrono::DateTime;
+    use pretty_assertions::assert_eq;
+
+    fn tx(amount: u32, merchant: &str, time: &str) -> TX {
+        TX::new(
+            amount,
+            merchant,
+            DateTime::parse_from_rfc3339(time).unwrap().into(),
+        )
+    }
+
+    fn account() -> Account {
+        Account::new(
+            1000,
+            true,
+            vec![
+                tx(10, "Netflix", "2019-02-13T11:00:00.000Z"),
+                tx(20, "Spotify", "2019-02-13T11:02:00.000Z"),
+                tx(30, "Netflix", "2019-02-13T11:04:00.000Z"),
+                tx(40, "Netflix", "2019-02-13T11:08:00.000Z"),
+            ],
+        )
+    }
+
+    fn validate(rule: &str, tx: &TX) -> Option<Violation> {
+        let rules = parse_rules(rule).unwrap();
+
+        rules[0].validate(&account(), tx)
+    }
+
+    #[test]
+    fn merchant_velocity() {
+        let rule =
+            "count(tx where merchant == tx.merchant, within 10m) > 2 => \"merchant-velocity\"";
+
+        assert_eq!(
+            validate(rule, &tx(5, "Netflix", "2019-02-13T11:09:00.000Z")),
+            Some(Violation::new("merchant-velocity"))
+        );
+        assert_eq!(
+            validate(rule, &tx(5, "Netflix", "2019-02-13T11:10:01.000Z")),
+            None
+        );
+        assert_eq!(
+            validate(rule, &tx(5, "Spotify", "2019-02-13T11:09:00.000Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn sum_amounts() {
+        let rule = "sum(tx where merchant == \"Netflix\") + 0 > 50 => \"too-much-netflix\"";
+        assert!(parse_rules(rule).is_err());
+
+        let rule = "sum(tx where merchant == \"Netflix\") >= 80 and tx.amount > 10 => \"too-much\"";
+
+        assert_eq!(
+            validate(rule, &tx(11, "Netflix", "2019-02-13T11:09:00.000Z")),
+            Some(Violation::new("too-much"))
+        );
+        assert_eq!(
+            validate(rule, &tx(10, "Netflix", "2019-02-13T11:09:00.000Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn account_fields_and_boolean_operators() {
+        let rule = "tx.amount > account.available_limit or not (tx.mcc != 7995) => \"risky\"";
+
+        assert_eq!(
+            validate(rule, &tx(1001, "Casino", "2019-02-13T11:09:00.000Z")),
+            Some(Violation::new("risky"))
+        );
+        assert_eq!(
+            validate(
+                rule,
+                &tx(5, "Casino", "2019-02-13T11:09:00.000Z").with_mcc(Some(7995))
+            ),
+            Some(Violation::new("risky"))
+        );
+        assert_eq!(
+            validate(rule, &tx(5, "Casino", "2019-02-13T11:09:00.000Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn decimal_amounts() {
+        let rule = "tx.amount >= 10.5 => \"large\"";
+
+        assert_eq!(
+            validate(rule, &tx(11, "Nike", "2019-02-13T11:09:00.000Z")),
+            Some(Violation::new("large"))
+        );
+        assert_eq!(
+            validate(rule, &tx(10, "Nike", "2019-02-13T11:09:00.000Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn skip_comments_and_blank_lines() {
+        let source = "# velocity rules\n\ncount(tx) > 10 => \"many-txs\"\n";
+
+        let rules = parse_rules(source).unwrap();
+
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].source(), "count(tx) > 10 => \"many-txs\"");
+        assert_eq!(rules[0].name(), rules[0].source());
+    }
+
+    #[test]
+    fn report_syntax_errors() {
+        let error = |source: &str| parse_rules(source).unwrap_err().to_string();
+
+        assert_eq!(
+            error("\ntx.amount > => \"x\""),
+            "line 2, column 13: expected a value, found '=>'"
+        );
+        assert_eq!(error("tx.amount > 10"), "line 1, column 15: expected '=>'");
+        assert_eq!(
+            error("tx.amount > 10 => \"\""),
+            "line 1, column 19: expected a quoted violation code, found \"\""
+        );
+        assert_eq!(
+            error("count(tx, within 10w) > 1 => \"x\""),
+            "line 1, column 18: unknown duration unit 'w'"
+        );
+        assert_eq!(
+            error("count(tx, within 9999999999999999d) > 1 => \"x\""),
+            "line 1, column 18: invalid duration '9999999999999999d'"
+        );
+        assert_eq!(
+            error("tx.amount > 0.0000000000000000001 => \"x\""),
+            "line 1, column 13: number '0.0000000000000000001' has more than 18 decimal places"
+        );
+    }
+
+    #[test]
+    fn report_type_errors() {
+        let error = |source: &str| parse_rules(source).unwrap_err().to_string();
+
+        assert_eq!(
+            error("tx.merchant > 10 => \"x\""),
+            "line 1, column 13: can't compare text with a number"
+        );
+        assert_eq!(
+            error("tx.merchant > \"a\" => \"x\""),
+            "line 1, column 13: text can only be compared for equality"
+        );
+        assert_eq!(
+            error("tx.amount => \"x\""),
+            "line 1, column 11: the rule condition must be a condition, not a number"
+        );
+        assert_eq!(
+            error("merchant == \"a\" => \"x\""),
+            "line 1, column 1: unknown name 'merchant', fields of the transaction are written as tx.merchant"
+        );
+        assert_eq!(
+            error("tx.limit > 1 => \"x\""),
+            "line 1, column 4: unknown field 'limit'"
+        );
+    }
+}
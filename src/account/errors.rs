@@ -1,3 +1,7 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -14,4 +18,147 @@ pub enum OperationError {
     HighFrequencySmallInterval,
     #[error("duplicated-tx")]
     DuplicatedTx,
+    #[error("limit-below-consumed")]
+    LimitBelowConsumed,
+    #[error("transaction-not-found")]
+    TransactionNotFound,
+    #[error("transaction-already-reversed")]
+    TransactionAlreadyReversed,
+    #[error("hold-already-exists")]
+    HoldAlreadyExists,
+    #[error("hold-not-found")]
+    HoldNotFound,
+    #[error("hold-expired")]
+    HoldExpired,
+    #[error("hold-already-settled")]
+    HoldAlreadySettled,
+    #[error("capture-exceeds-hold")]
+    CaptureExceedsHold,
+    #[error("invalid-amount")]
+    InvalidAmount,
+    #[error("currency-mismatch")]
+    CurrencyMismatch,
+    #[error("blocked-merchant-category")]
+    BlockedMerchantCategory,
+    #[error("daily-limit-exceeded")]
+    DailyLimitExceeded,
+    #[error("monthly-limit-exceeded")]
+    MonthlyLimitExceeded,
+    #[error("merchant-limit-exceeded")]
+    MerchantLimitExceeded,
+}
+
+/// Reason an operation was rejected, identified by a kebab-case code. Built-in violations come
+/// from [`OperationError`], while custom rules can report codes of their own along with
+/// structured detail.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "ViolationOutput", into = "ViolationOutput")]
+pub struct Violation {
+    code: String,
+    detail: Option<Value>,
+}
+
+impl Violation {
+    pub fn new(code: &str) -> Self {
+        Self {
+            code: code.to_string(),
+            detail: None,
+        }
+    }
+
+    pub fn with_detail(mut self, detail: Value) -> Self {
+        self.detail = Some(detail);
+        self
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn detail(&self) -> Option<&Value> {
+        self.detail.as_ref()
+    }
+}
+
+impl From<OperationError> for Violation {
+    fn from(error: OperationError) -> Self {
+        Violation::new(&error.to_string())
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.code)
+    }
+}
+
+/// Violations without detail are written as their bare code, as the built-in ones always were.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ViolationOutput {
+    Code(String),
+    Detailed { code: String, detail: Value },
+}
+
+impl From<ViolationOutput> for Violation {
+    fn from(output: ViolationOutput) -> Self {
+        match output {
+            ViolationOutput::Code(code) => Violation { code, detail: None },
+            ViolationOutput::Detailed { code, detail } => Violation {
+                code,
+                detail: Some(detail),
+            },
+        }
+    }
+}
+
+impl From<Violation> for ViolationOutput {
+    fn from(violation: Violation) -> Self {
+        match violation.detail {
+            None => ViolationOutput::Code(violation.code),
+            Some(detail) => ViolationOutput::Detailed {
+                code: violation.code,
+                detail,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod violation {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn create_from_operation_error() {
+        let violation = Violation::from(OperationError::InsufficientLimit);
+
+        assert_eq!(violation.code(), "insufficient-limit");
+        assert_eq!(violation.detail(), None);
+    }
+
+    #[test]
+    fn serialize_code() {
+        let violation = Violation::from(OperationError::DuplicatedTx);
+
+        assert_eq!(
+            serde_json::to_string(&violation).unwrap(),
+            "\"duplicated-tx\""
+        );
+    }
+
+    #[test]
+    fn serialize_detail() {
+        let violation = Violation::new("merchant-velocity").with_detail(json!({"count": 3}));
+
+        let json = serde_json::to_string(&violation).unwrap();
+
+        assert_eq!(
+            json,
+            "{\"code\":\"merchant-velocity\",\"detail\":{\"count\":3}}"
+        );
+        assert_eq!(serde_json::from_str::<Violation>(&json).unwrap(), violation);
+    }
 }
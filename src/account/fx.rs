@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::account::{Currency, Decimal, Money};
+
+/// Exchange rates used to convert foreign-currency transactions to the account currency.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "FxRatesFile")]
+pub struct FxRates {
+    /// Percentage charged on top of every converted amount.
+    markup_percent: Decimal,
+    rates: HashMap<(Currency, Currency), Decimal>,
+}
+
+/// Rates as written in the rate table file, keyed by `"<from>/<to>"` currency pairs.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct FxRatesFile {
+    #[serde(default)]
+    markup_percent: Decimal,
+    rates: HashMap<String, Decimal>,
+}
+
+impl TryFrom<FxRatesFile> for FxRates {
+    type Error = String;
+
+    fn try_from(file: FxRatesFile) -> Result<Self, Self::Error> {
+        let rates = file
+            .rates
+            .into_iter()
+            .map(|(pair, rate)| {
+                let (from, to) = pair
+                    .split_once('/')
+                    .and_then(|(from, to)| {
+                        Some((Currency::from_code(from)?, Currency::from_code(to)?))
+                    })
+                    .ok_or_else(|| format!("invalid currency pair '{}'", pair))?;
+
+                Ok(((from, to), rate))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self {
+            markup_percent: file.markup_percent,
+            rates,
+        })
+    }
+}
+
+impl FxRates {
+    /// Converts `amount` to `currency` with the markup applied, if there's a rate for the pair.
+    pub fn convert(&self, amount: Money, currency: Currency) -> Option<Money> {
+        let rate = self.rate(amount.currency(), currency)?;
+
+        amount.convert(currency, rate, self.markup_percent)
+    }
+
+    pub fn rate(&self, from: Currency, to: Currency) -> Option<Decimal> {
+        self.rates.get(&(from, to)).copied()
+    }
+
+    pub fn markup_percent(&self) -> Decimal {
+        self.markup_percent
+    }
+}
+
+#[cfg(test)]
+mod fx_rates {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn currency(code: &str) -> Currency {
+        Currency::from_code(code).unwrap()
+    }
+
+    fn fx_rates(markup_percent: &str) -> FxRates {
+        FxRates {
+            markup_percent: markup_percent.parse().unwrap(),
+            rates: HashMap::from([
+                (
+                    (currency("EUR"), currency("USD")),
+                    "1.0841".parse().unwrap(),
+                ),
+                (
+                    (currency("USD"), currency("JPY")),
+                    "151.37".parse().unwrap(),
+                ),
+            ]),
+        }
+    }
+
+    #[test]
+    fn convert_amount() {
+        let converted = fx_rates("0").convert(Money::new(2000, currency("EUR")), Currency::USD);
+
+        assert_eq!(converted, Some(Money::new(2168, Currency::USD)));
+    }
+
+    #[test]
+    fn convert_amount_with_markup() {
+        let converted = fx_rates("2.5").convert(Money::new(2000, currency("EUR")), Currency::USD);
+
+        assert_eq!(converted, Some(Money::new(2222, Currency::USD)));
+    }
+
+    #[test]
+    fn convert_between_currency_exponents() {
+        let converted = fx_rates("0").convert(Money::new(1050, Currency::USD), currency("JPY"));
+
+        assert_eq!(converted, Some(Money::new(1589, currency("JPY"))));
+    }
+
+    #[test]
+    fn convert_without_rate() {
+        let converted = fx_rates("0").convert(Money::new(1000, Currency::USD), currency("EUR"));
+
+        assert_eq!(converted, None);
+    }
+
+    #[test]
+    fn deserialize_rates() {
+        let rates: FxRates = serde_json::from_str(
+            "{\"markup-percent\": 2.5, \"rates\": {\"EUR/USD\": 1.0841, \"USD/JPY\": \"151.37\"}}",
+        )
+        .unwrap();
+
+        assert_eq!(rates, fx_rates("2.5"));
+    }
+
+    #[test]
+    fn reject_invalid_currency_pair() {
+        let rates = serde_json::from_str::<FxRates>("{\"rates\": {\"EURUSD\": 1.0841}}");
+
+        assert!(rates.is_err());
+    }
+}
@@ -1,94 +1,566 @@
 use chrono::{DateTime, Utc};
-use serde::Serialize;
-use std::{fmt::Debug, ops::Sub};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Debug, ops::Sub, sync::Arc};
 
 use crate::account::{
-    AccountRule, DuplicatedTx, HighFrequencySmallInterval, InsufficientLimit, OperationError,
+    AccountRule, Currency, Money, OperationError, RuleTrace, RulesConfig, Violation,
 };
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct TX {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub merchant: String,
-    pub amount: u32,
+    pub amount: Money,
     pub time: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcc: Option<u16>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reversed: bool,
 }
 
 impl TX {
-    pub fn new(amount: u32, merchant: &str, time: DateTime<Utc>) -> Self {
+    pub fn new(amount: impl Into<Money>, merchant: &str, time: DateTime<Utc>) -> Self {
         Self {
-            amount,
+            id: None,
+            amount: amount.into(),
             merchant: merchant.to_string(),
             time,
+            mcc: None,
+            reversed: false,
         }
     }
 
+    pub fn with_id(mut self, id: Option<String>) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Sets the ISO 18245 merchant category code of the transaction.
+    pub fn with_mcc(mut self, mcc: Option<u16>) -> Self {
+        self.mcc = mcc;
+        self
+    }
+
     pub fn seconds_since(&self, another: &TX) -> i64 {
         self.time.sub(another.time).num_seconds()
     }
 }
 
-#[derive(Debug)]
+/// Groups of merchant category codes cardholders can block transactions for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MerchantCategory {
+    Gambling,
+    Crypto,
+    Adult,
+    CashAdvance,
+    MoneyTransfer,
+    AlcoholTobacco,
+}
+
+impl MerchantCategory {
+    pub fn mccs(&self) -> &'static [u16] {
+        match self {
+            MerchantCategory::Gambling => &[7800, 7801, 7802, 7995, 9406],
+            MerchantCategory::Crypto => &[6051],
+            MerchantCategory::Adult => &[5967],
+            MerchantCategory::CashAdvance => &[6010, 6011],
+            MerchantCategory::MoneyTransfer => &[4829],
+            MerchantCategory::AlcoholTobacco => &[5921, 5993],
+        }
+    }
+
+    pub fn contains(&self, mcc: u16) -> bool {
+        self.mccs().contains(&mcc)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HoldStatus {
+    Active,
+    Captured,
+    Released,
+    Expired,
+}
+
+/// Amount reserved from the available limit until it is captured, released or expires.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Hold {
+    pub id: String,
+    pub merchant: String,
+    pub amount: Money,
+    pub time: DateTime<Utc>,
+    pub status: HoldStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcc: Option<u16>,
+}
+
+impl Hold {
+    pub fn new(id: &str, amount: impl Into<Money>, merchant: &str, time: DateTime<Utc>) -> Self {
+        Self {
+            id: id.to_string(),
+            merchant: merchant.to_string(),
+            amount: amount.into(),
+            time,
+            status: HoldStatus::Active,
+            mcc: None,
+        }
+    }
+
+    pub fn with_mcc(mut self, mcc: Option<u16>) -> Self {
+        self.mcc = mcc;
+        self
+    }
+
+    pub fn to_tx(&self) -> TX {
+        TX::new(self.amount, &self.merchant, self.time)
+            .with_id(Some(self.id.clone()))
+            .with_mcc(self.mcc)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", from = "AccountSnapshot")]
 pub struct Account {
-    pub available_limit: u32,
+    pub total_limit: Money,
+    pub available_limit: Money,
     pub active_card: bool,
     pub txs: Vec<TX>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub holds: Vec<Hold>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub blocked_categories: Vec<MerchantCategory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_limit: Option<Money>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_limit: Option<Money>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    processed_txs: HashMap<String, AccountState>,
+    #[serde(rename = "rules")]
+    rules_config: RulesConfig,
+    #[serde(skip)]
     rules: Vec<Box<dyn AccountRule>>,
+    #[serde(skip)]
+    custom_rules: Vec<Arc<dyn AccountRule>>,
+    #[serde(skip)]
+    shadow_rules: Vec<Arc<dyn AccountRule>>,
 }
 
 impl PartialEq for Account {
     fn eq(&self, other: &Self) -> bool {
-        self.available_limit == other.available_limit
+        self.total_limit == other.total_limit
+            && self.available_limit == other.available_limit
             && self.active_card == other.active_card
             && self.txs == other.txs
+            && self.holds == other.holds
+            && self.blocked_categories == other.blocked_categories
+            && self.daily_limit == other.daily_limit
+            && self.monthly_limit == other.monthly_limit
+            && self.processed_txs == other.processed_txs
+            && self.rules_config == other.rules_config
+    }
+}
+
+/// Serialized form of an [`Account`], whose rules are rebuilt from its configuration.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct AccountSnapshot {
+    total_limit: Option<Money>,
+    available_limit: Money,
+    active_card: bool,
+    txs: Vec<TX>,
+    #[serde(default)]
+    holds: Vec<Hold>,
+    #[serde(default)]
+    blocked_categories: Vec<MerchantCategory>,
+    #[serde(default)]
+    daily_limit: Option<Money>,
+    #[serde(default)]
+    monthly_limit: Option<Money>,
+    #[serde(default)]
+    processed_txs: HashMap<String, AccountState>,
+    #[serde(default)]
+    rules: RulesConfig,
+}
+
+impl From<AccountSnapshot> for Account {
+    fn from(snapshot: AccountSnapshot) -> Self {
+        let mut account =
+            Account::new(snapshot.available_limit, snapshot.active_card, snapshot.txs)
+                .with_rules_config(snapshot.rules);
+
+        if let Some(total_limit) = snapshot.total_limit {
+            account.total_limit = total_limit;
+        }
+
+        account.holds = snapshot.holds;
+        account.blocked_categories = snapshot.blocked_categories;
+        account.daily_limit = snapshot.daily_limit;
+        account.monthly_limit = snapshot.monthly_limit;
+        account.processed_txs = snapshot.processed_txs;
+
+        account
     }
 }
 
 impl Account {
-    pub fn new(available_limit: u32, active_card: bool, txs: Vec<TX>) -> Self {
+    pub fn new(available_limit: impl Into<Money>, active_card: bool, txs: Vec<TX>) -> Self {
+        let available_limit = available_limit.into();
+        let rules_config = RulesConfig::default();
+
         Self {
+            total_limit: available_limit,
             available_limit,
             active_card,
             txs,
-            rules: vec![
-                InsufficientLimit::boxed(),
-                HighFrequencySmallInterval::boxed(),
-                DuplicatedTx::boxed(),
-            ],
+            holds: vec![],
+            blocked_categories: vec![],
+            daily_limit: None,
+            monthly_limit: None,
+            processed_txs: HashMap::new(),
+            rules: rules_config.rules(),
+            rules_config,
+            custom_rules: vec![],
+            shadow_rules: vec![],
         }
     }
 
-    pub fn execute_tx(&mut self, tx: TX) -> Result<AccountState, Vec<OperationError>> {
-        let errors: Vec<OperationError> = self
-            .rules
+    pub fn with_blocked_categories(mut self, blocked_categories: Vec<MerchantCategory>) -> Self {
+        self.blocked_categories = blocked_categories;
+        self
+    }
+
+    /// Caps the total spent per calendar day and month, on top of the credit limit.
+    pub fn with_spending_caps(
+        mut self,
+        daily_limit: Option<Money>,
+        monthly_limit: Option<Money>,
+    ) -> Self {
+        self.daily_limit = daily_limit;
+        self.monthly_limit = monthly_limit;
+        self
+    }
+
+    /// Sets rules that aren't part of the rules configuration, run after the configured ones.
+    pub fn with_custom_rules(mut self, custom_rules: Vec<Arc<dyn AccountRule>>) -> Self {
+        self.custom_rules = custom_rules;
+        self
+    }
+
+    /// Rules whose violations are reported apart, without declining any transaction.
+    pub fn with_shadow_rules(mut self, shadow_rules: Vec<Arc<dyn AccountRule>>) -> Self {
+        self.shadow_rules = shadow_rules;
+        self
+    }
+
+    pub fn with_rules_config(mut self, rules_config: RulesConfig) -> Self {
+        self.rules = rules_config.rules();
+        self.rules_config = rules_config;
+        self
+    }
+
+    pub fn execute_tx(&mut self, tx: TX) -> Result<AccountState, Vec<Violation>> {
+        let (result, _) = self.execute_traced_tx(tx, false);
+
+        result
+    }
+
+    /// Executes `tx` like [`Account::execute_tx`], also returning the trace of every active and
+    /// shadow rule, each checked once against it.
+    pub fn execute_traced_tx(
+        &mut self,
+        tx: TX,
+        explain: bool,
+    ) -> (Result<AccountState, Vec<Violation>>, Vec<RuleTrace>) {
+        let trace = self.trace_tx(&tx, explain);
+
+        if self.is_inactive() {
+            return (Err(vec![OperationError::InactiveCard.into()]), trace);
+        }
+
+        let errors: Vec<Violation> = trace
             .iter()
-            .filter_map(|r| r.validate(self, &tx))
+            .filter(|t| !t.is_shadow())
+            .filter_map(|t| t.violation().cloned())
             .collect();
 
+        if !errors.is_empty() {
+            return (Err(errors), trace);
+        }
+
+        (self.apply_tx(tx).map_err(|error| vec![error.into()]), trace)
+    }
+
+    /// Takes an authorized transaction from the available limit without checking the rules.
+    pub fn apply_tx(&mut self, tx: TX) -> Result<AccountState, OperationError> {
+        self.ensure_currency(&tx.amount)?;
+
+        self.available_limit = self.available_limit.saturating_sub(tx.amount);
+        self.txs.push(tx);
+
+        Ok(self.to_state())
+    }
+
+    /// Currency of the account limits, which every transaction must be authorized in.
+    pub fn currency(&self) -> Currency {
+        self.total_limit.currency()
+    }
+
+    pub fn ensure_currency(&self, amount: &Money) -> Result<(), OperationError> {
+        if amount.currency() != self.currency() {
+            return Err(OperationError::CurrencyMismatch);
+        }
+
+        Ok(())
+    }
+
+    fn all_rules(&self) -> impl Iterator<Item = &dyn AccountRule> {
+        self.rules
+            .iter()
+            .map(|r| r.as_ref())
+            .chain(self.custom_rules.iter().map(|r| r.as_ref()))
+    }
+
+    fn validate_tx(&self, tx: &TX) -> Vec<Violation> {
+        if let Err(error) = self.ensure_currency(&tx.amount) {
+            return vec![error.into()];
+        }
+
+        self.all_rules()
+            .filter_map(|r| r.validate(self, tx))
+            .collect()
+    }
+
+    /// Whether `tx` is rejected before the rules are checked against it.
+    fn skips_rules(&self, tx: &TX) -> bool {
+        self.is_inactive() || self.ensure_currency(&tx.amount).is_err()
+    }
+
+    /// Checks every active and shadow rule against `tx` without executing it, recording each
+    /// verdict, and its evidence when `explain` is set. No rule runs for an inactive card or a
+    /// transaction in another currency.
+    pub fn trace_tx(&self, tx: &TX, explain: bool) -> Vec<RuleTrace> {
+        let shadow_rules = self.shadow_rules.iter().map(|r| r.as_ref());
+
+        if self.skips_rules(tx) {
+            return self
+                .all_rules()
+                .map(|r| RuleTrace::not_run(r.name()))
+                .chain(shadow_rules.map(|r| RuleTrace::not_run(r.name()).into_shadow()))
+                .collect();
+        }
+
+        let check = |rule: &dyn AccountRule| {
+            if explain {
+                rule.explain(self, tx)
+            } else {
+                RuleTrace::new(rule.name(), rule.validate(self, tx))
+            }
+        };
+
+        self.all_rules()
+            .map(check)
+            .chain(shadow_rules.map(|r| check(r).into_shadow()))
+            .collect()
+    }
+
+    /// Reserves the hold amount from the available limit, if the account rules authorize it as
+    /// a transaction.
+    pub fn place_hold(&mut self, hold: Hold) -> Result<AccountState, Vec<Violation>> {
+        if self.holds.iter().any(|h| h.id == hold.id) {
+            return Err(vec![OperationError::HoldAlreadyExists.into()]);
+        }
+
+        let errors = self.validate_tx(&hold.to_tx());
+
         if !errors.is_empty() {
             return Err(errors);
         }
 
-        self.available_limit -= tx.amount;
+        self.apply_hold(hold).map_err(|error| vec![error.into()])
+    }
+
+    /// Reserves the hold amount from the available limit without checking the rules.
+    pub fn apply_hold(&mut self, hold: Hold) -> Result<AccountState, OperationError> {
+        if self.holds.iter().any(|h| h.id == hold.id) {
+            return Err(OperationError::HoldAlreadyExists);
+        }
+
+        self.ensure_currency(&hold.amount)?;
+
+        self.available_limit = self.available_limit.saturating_sub(hold.amount);
+        self.holds.push(hold);
+
+        Ok(self.to_state())
+    }
+
+    /// Settles an active hold for `amount`, giving back whatever was reserved on top of it and
+    /// recording the captured amount as an authorized transaction.
+    pub fn capture_hold(
+        &mut self,
+        hold_id: &str,
+        amount: impl Into<Money>,
+        time: DateTime<Utc>,
+    ) -> Result<AccountState, OperationError> {
+        let amount = amount.into();
+        self.ensure_currency(&amount)?;
+
+        let hold = self.active_hold_mut(hold_id)?;
+
+        if amount > hold.amount {
+            return Err(OperationError::CaptureExceedsHold);
+        }
+
+        hold.status = HoldStatus::Captured;
+
+        let held_amount = hold.amount;
+        let tx = TX::new(amount, &hold.merchant, time)
+            .with_id(Some(hold.id.clone()))
+            .with_mcc(hold.mcc);
+
+        self.restore_limit(held_amount.saturating_sub(amount));
         self.txs.push(tx);
 
-        Ok(AccountState::new(
-            self.active_card,
-            self.available_limit,
-            vec![],
-        ))
+        Ok(self.to_state())
+    }
+
+    pub fn release_hold(&mut self, hold_id: &str) -> Result<AccountState, OperationError> {
+        let hold = self.active_hold_mut(hold_id)?;
+
+        hold.status = HoldStatus::Released;
+
+        let held_amount = hold.amount;
+        self.restore_limit(held_amount);
+
+        Ok(self.to_state())
+    }
+
+    /// Releases every active hold placed more than `expiry_seconds` before `now`.
+    pub fn expire_holds(&mut self, now: DateTime<Utc>, expiry_seconds: i64) {
+        let mut expired_amount = Money::zero(self.currency());
+
+        for hold in self.holds.iter_mut() {
+            if hold.status == HoldStatus::Active
+                && now.sub(hold.time).num_seconds() > expiry_seconds
+            {
+                hold.status = HoldStatus::Expired;
+                expired_amount = expired_amount.saturating_add(hold.amount);
+            }
+        }
+
+        self.restore_limit(expired_amount);
+    }
+
+    fn active_hold_mut(&mut self, hold_id: &str) -> Result<&mut Hold, OperationError> {
+        let hold = self
+            .holds
+            .iter_mut()
+            .find(|h| h.id == hold_id)
+            .ok_or(OperationError::HoldNotFound)?;
+
+        match hold.status {
+            HoldStatus::Active => Ok(hold),
+            HoldStatus::Expired => Err(OperationError::HoldExpired),
+            HoldStatus::Captured | HoldStatus::Released => Err(OperationError::HoldAlreadySettled),
+        }
+    }
+
+    fn restore_limit(&mut self, amount: Money) {
+        let restored_limit = self.available_limit.saturating_add(amount);
+
+        self.available_limit = if restored_limit > self.total_limit {
+            self.total_limit
+        } else {
+            restored_limit
+        };
+    }
+
+    /// Returns the result originally given to the transaction with the same idempotency key.
+    pub fn processed_tx_state(&self, tx_id: &str) -> Option<&AccountState> {
+        self.processed_txs.get(tx_id)
+    }
+
+    pub fn record_processed_tx(&mut self, tx_id: String, state: AccountState) {
+        self.processed_txs.insert(tx_id, state);
+    }
+
+    /// Sets a new credit limit, keeping the amount already consumed from the previous one.
+    pub fn update_credit_limit(
+        &mut self,
+        total_limit: impl Into<Money>,
+    ) -> Result<AccountState, OperationError> {
+        let total_limit = total_limit.into();
+        let consumed = self.consumed_limit();
+
+        self.ensure_currency(&total_limit)?;
+
+        if total_limit < consumed {
+            return Err(OperationError::LimitBelowConsumed);
+        }
+
+        self.total_limit = total_limit;
+        self.available_limit = total_limit.saturating_sub(consumed);
+
+        Ok(self.to_state())
+    }
+
+    /// Gives back the amount of a previously authorized transaction to the available limit.
+    pub fn refund_tx(&mut self, tx_id: &str) -> Result<AccountState, OperationError> {
+        let index = self
+            .txs
+            .iter()
+            .position(|t| t.id.as_deref() == Some(tx_id))
+            .ok_or(OperationError::TransactionNotFound)?;
+
+        if self.txs[index].reversed {
+            return Err(OperationError::TransactionAlreadyReversed);
+        }
+
+        let amount = self.txs[index].amount;
+        self.ensure_currency(&amount)?;
+
+        self.txs[index].reversed = true;
+        self.restore_limit(amount);
+
+        Ok(self.to_state())
     }
 
-    pub fn get_last_n_txs(&self, window_size: usize) -> (&TX, &TX) {
-        let total_txs = self.txs.len();
+    /// Sum of the authorized transactions that weren't reversed and match `filter`.
+    pub fn spent_where(&self, filter: impl Fn(&TX) -> bool) -> Money {
+        self.txs
+            .iter()
+            .filter(|t| !t.reversed && filter(t))
+            .fold(Money::zero(self.currency()), |spent, t| {
+                spent.saturating_add(t.amount)
+            })
+    }
 
-        let initial_window_tx = self.txs.get(total_txs - window_size).unwrap();
-        let end_window_tx = self.txs.get(total_txs - 1).unwrap();
+    /// Amount reserved by the active holds matching `filter`, as the transactions they become
+    /// once captured.
+    pub fn held_where(&self, filter: impl Fn(&TX) -> bool) -> Money {
+        self.holds
+            .iter()
+            .filter(|h| h.status == HoldStatus::Active)
+            .map(Hold::to_tx)
+            .filter(|t| filter(t))
+            .fold(Money::zero(self.currency()), |held, t| {
+                held.saturating_add(t.amount)
+            })
+    }
 
-        (initial_window_tx, end_window_tx)
+    pub fn consumed_limit(&self) -> Money {
+        self.total_limit.saturating_sub(self.available_limit)
     }
 
-    pub fn to_invalid_state(&self, errors: Vec<OperationError>) -> AccountState {
-        AccountState::new(self.active_card, self.available_limit, errors)
+    pub fn to_invalid_state(&self, violations: Vec<impl Into<Violation>>) -> AccountState {
+        AccountState::from_violations(
+            self.active_card,
+            self.available_limit,
+            violations.into_iter().map(Into::into).collect(),
+        )
     }
 
     pub fn to_state(&self) -> AccountState {
@@ -100,35 +572,121 @@ impl Account {
     }
 }
 
-#[derive(Debug, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AccountState {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    account_id: String,
     active_card: bool,
-    available_limit: u32,
-    violations: Vec<String>,
+    available_limit: Money,
+    violations: Vec<Violation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    converted_amount: Option<Money>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    shadow_violations: Vec<Violation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    trace: Vec<RuleTrace>,
 }
 
 impl AccountState {
-    pub fn new(active_card: bool, available_limit: u32, errors: Vec<OperationError>) -> Self {
-        Self {
+    pub fn new(
+        active_card: bool,
+        available_limit: impl Into<Money>,
+        errors: Vec<OperationError>,
+    ) -> Self {
+        Self::from_violations(
+            active_card,
             available_limit,
+            errors.into_iter().map(Violation::from).collect(),
+        )
+    }
+
+    pub fn from_violations(
+        active_card: bool,
+        available_limit: impl Into<Money>,
+        violations: Vec<Violation>,
+    ) -> Self {
+        Self {
+            account_id: String::new(),
+            available_limit: available_limit.into(),
             active_card,
-            violations: errors.iter().map(|e| e.to_string()).collect(),
+            violations,
+            converted_amount: None,
+            shadow_violations: vec![],
+            trace: vec![],
         }
     }
 
+    pub fn with_account_id(mut self, account_id: &str) -> Self {
+        self.account_id = account_id.to_string();
+        self
+    }
+
+    /// Records the transaction amount converted to the account currency.
+    pub fn with_converted_amount(mut self, converted_amount: Money) -> Self {
+        self.converted_amount = Some(converted_amount);
+        self
+    }
+
+    /// Records the violations of shadow rules, which didn't decline the transaction.
+    pub fn with_shadow_violations(mut self, shadow_violations: Vec<Violation>) -> Self {
+        self.shadow_violations = shadow_violations;
+        self
+    }
+
+    /// Records the verdict of every rule for the transaction, in explain mode.
+    pub fn with_trace(mut self, trace: Vec<RuleTrace>) -> Self {
+        self.trace = trace;
+        self
+    }
+
     pub fn not_initialized() -> Self {
         AccountState::new(false, 0, vec![OperationError::AccountNotInitialized])
     }
 
-    pub fn inactive(available_limit: u32) -> Self {
+    pub fn inactive(available_limit: impl Into<Money>) -> Self {
         AccountState::new(false, available_limit, vec![OperationError::InactiveCard])
     }
+
+    pub fn account_id(&self) -> &str {
+        &self.account_id
+    }
+
+    pub fn active_card(&self) -> bool {
+        self.active_card
+    }
+
+    pub fn available_limit(&self) -> Money {
+        self.available_limit
+    }
+
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    pub fn converted_amount(&self) -> Option<Money> {
+        self.converted_amount
+    }
+
+    pub fn shadow_violations(&self) -> &[Violation] {
+        &self.shadow_violations
+    }
+
+    pub fn trace(&self) -> &[RuleTrace] {
+        &self.trace
+    }
 }
 
 #[cfg(test)]
 mod account {
     use super::*;
 
+    use crate::account::{
+        BlockedMerchantCategory, DailyLimit, DuplicatedTx, HighFrequencySmallInterval,
+        InsufficientLimit, MonthlyLimit,
+    };
+
+    use chrono_tz::Tz;
+
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -140,14 +698,26 @@ mod account {
         );
 
         let expected_account = Account {
-            available_limit: 100,
+            total_limit: Money::from(100),
+            available_limit: Money::from(100),
             active_card: true,
             txs: vec![TX::new(50, "Merchant X", DateTime::default())],
+            holds: vec![],
+            blocked_categories: vec![],
+            daily_limit: None,
+            monthly_limit: None,
+            processed_txs: HashMap::new(),
+            rules_config: RulesConfig::default(),
             rules: vec![
                 InsufficientLimit::boxed(),
-                HighFrequencySmallInterval::boxed(),
-                DuplicatedTx::boxed(),
+                HighFrequencySmallInterval::boxed(3, 120),
+                DuplicatedTx::boxed(120),
+                BlockedMerchantCategory::boxed(),
+                DailyLimit::boxed(Tz::UTC),
+                MonthlyLimit::boxed(Tz::UTC),
             ],
+            custom_rules: vec![],
+            shadow_rules: vec![],
         };
 
         assert_eq!(account, expected_account);
@@ -166,6 +736,22 @@ mod account {
         assert_eq!(account_state, expected_account_state);
     }
 
+    #[test]
+    fn execute_tx_with_custom_rules() {
+        let mut rules_config = RulesConfig::default();
+        rules_config.insufficient_limit.enabled = false;
+
+        let mut account = Account::new(100, true, vec![]).with_rules_config(rules_config);
+
+        let account_state = account
+            .execute_tx(TX::new(150, "Merchant X", DateTime::default()))
+            .unwrap();
+
+        let expected_account_state = AccountState::new(true, 0, vec![]);
+
+        assert_eq!(account_state, expected_account_state);
+    }
+
     #[test]
     fn execute_tx_return_errors() {
         let mut account = Account::new(100, true, vec![]);
@@ -174,28 +760,233 @@ mod account {
             .execute_tx(TX::new(150, "Merchant X", DateTime::default()))
             .unwrap_err();
 
-        let expected_errors = vec![OperationError::InsufficientLimit];
+        let expected_errors = vec![Violation::from(OperationError::InsufficientLimit)];
 
         assert_eq!(errors, expected_errors);
     }
 
     #[test]
-    fn get_last_n_txs() {
-        let account = Account::new(
-            1000,
-            true,
-            vec![
-                TX::new(100, "Merchant X", DateTime::default()),
-                TX::new(50, "Merchant Y", DateTime::default()),
-                TX::new(25, "Merchant Z", DateTime::default()),
-                TX::new(200, "Merchant ZZ", DateTime::default()),
-            ],
+    fn raise_credit_limit() {
+        let mut account = Account::new(100, true, vec![]);
+        account
+            .execute_tx(TX::new(30, "Merchant X", DateTime::default()))
+            .unwrap();
+
+        let account_state = account.update_credit_limit(200).unwrap();
+
+        assert_eq!(account_state, AccountState::new(true, 170, vec![]));
+        assert_eq!(account.total_limit, Money::from(200));
+        assert_eq!(account.consumed_limit(), Money::from(30));
+    }
+
+    #[test]
+    fn lower_credit_limit() {
+        let mut account = Account::new(100, true, vec![]);
+        account
+            .execute_tx(TX::new(30, "Merchant X", DateTime::default()))
+            .unwrap();
+
+        let account_state = account.update_credit_limit(30).unwrap();
+
+        assert_eq!(account_state, AccountState::new(true, 0, vec![]));
+    }
+
+    #[test]
+    fn lower_credit_limit_below_consumed() {
+        let mut account = Account::new(100, true, vec![]);
+        account
+            .execute_tx(TX::new(30, "Merchant X", DateTime::default()))
+            .unwrap();
+
+        let error = account.update_credit_limit(29).unwrap_err();
+
+        assert_eq!(error, OperationError::LimitBelowConsumed);
+        assert_eq!(account.total_limit, Money::from(100));
+        assert_eq!(account.available_limit, Money::from(70));
+    }
+
+    #[test]
+    fn refund_tx() {
+        let mut account = Account::new(100, true, vec![]);
+        account
+            .execute_tx(
+                TX::new(30, "Merchant X", DateTime::default()).with_id(Some(String::from("tx-1"))),
+            )
+            .unwrap();
+
+        let account_state = account.refund_tx("tx-1").unwrap();
+
+        assert_eq!(account_state, AccountState::new(true, 100, vec![]));
+        assert!(account.txs[0].reversed);
+    }
+
+    #[test]
+    fn refund_unknown_tx() {
+        let mut account = Account::new(100, true, vec![]);
+        account
+            .execute_tx(
+                TX::new(30, "Merchant X", DateTime::default()).with_id(Some(String::from("tx-1"))),
+            )
+            .unwrap();
+
+        let error = account.refund_tx("tx-2").unwrap_err();
+
+        assert_eq!(error, OperationError::TransactionNotFound);
+        assert_eq!(account.available_limit, Money::from(70));
+    }
+
+    #[test]
+    fn refund_already_reversed_tx() {
+        let mut account = Account::new(100, true, vec![]);
+        account
+            .execute_tx(
+                TX::new(30, "Merchant X", DateTime::default()).with_id(Some(String::from("tx-1"))),
+            )
+            .unwrap();
+        account.refund_tx("tx-1").unwrap();
+
+        let error = account.refund_tx("tx-1").unwrap_err();
+
+        assert_eq!(error, OperationError::TransactionAlreadyReversed);
+        assert_eq!(account.available_limit, Money::from(100));
+    }
+
+    #[test]
+    fn place_hold() {
+        let mut account = Account::new(100, true, vec![]);
+
+        let account_state = account
+            .place_hold(Hold::new("h-1", 60, "Hotel", DateTime::default()))
+            .unwrap();
+
+        assert_eq!(account_state, AccountState::new(true, 40, vec![]));
+        assert_eq!(account.holds[0].status, HoldStatus::Active);
+        assert!(account.txs.is_empty());
+    }
+
+    #[test]
+    fn place_hold_return_errors() {
+        let mut account = Account::new(100, true, vec![]);
+        account
+            .place_hold(Hold::new("h-1", 60, "Hotel", DateTime::default()))
+            .unwrap();
+
+        let insufficient_limit = account
+            .place_hold(Hold::new("h-2", 60, "Hotel", DateTime::default()))
+            .unwrap_err();
+        let already_exists = account
+            .place_hold(Hold::new("h-1", 10, "Hotel", DateTime::default()))
+            .unwrap_err();
+
+        assert_eq!(
+            insufficient_limit,
+            vec![Violation::from(OperationError::InsufficientLimit)]
+        );
+        assert_eq!(
+            already_exists,
+            vec![Violation::from(OperationError::HoldAlreadyExists)]
+        );
+    }
+
+    #[test]
+    fn capture_hold_for_less() {
+        let mut account = Account::new(100, true, vec![]);
+        account
+            .place_hold(Hold::new("h-1", 60, "Hotel", DateTime::default()))
+            .unwrap();
+
+        let account_state = account
+            .capture_hold("h-1", 45, DateTime::default())
+            .unwrap();
+
+        assert_eq!(account_state, AccountState::new(true, 55, vec![]));
+        assert_eq!(account.holds[0].status, HoldStatus::Captured);
+        assert_eq!(
+            account.txs,
+            vec![TX::new(45, "Hotel", DateTime::default()).with_id(Some(String::from("h-1")))]
+        );
+    }
+
+    #[test]
+    fn place_and_capture_hold_with_mcc() {
+        let mut account = Account::new(100, true, vec![])
+            .with_blocked_categories(vec![MerchantCategory::Gambling]);
+
+        let blocked = account
+            .place_hold(Hold::new("h-1", 60, "Casino", DateTime::default()).with_mcc(Some(7995)))
+            .unwrap_err();
+        account
+            .place_hold(Hold::new("h-2", 60, "Hotel", DateTime::default()).with_mcc(Some(7011)))
+            .unwrap();
+        account
+            .capture_hold("h-2", 45, DateTime::default())
+            .unwrap();
+
+        assert_eq!(
+            blocked,
+            vec![Violation::from(OperationError::BlockedMerchantCategory)]
+        );
+        assert_eq!(
+            account.txs,
+            vec![TX::new(45, "Hotel", DateTime::default())
+                .with_id(Some(String::from("h-2")))
+                .with_mcc(Some(7011))]
         );
+    }
+
+    #[test]
+    fn capture_hold_return_errors() {
+        let mut account = Account::new(100, true, vec![]);
+        account
+            .place_hold(Hold::new("h-1", 60, "Hotel", DateTime::default()))
+            .unwrap();
+
+        let exceeds_hold = account
+            .capture_hold("h-1", 61, DateTime::default())
+            .unwrap_err();
+        let not_found = account
+            .capture_hold("h-2", 10, DateTime::default())
+            .unwrap_err();
+        account.release_hold("h-1").unwrap();
+        let already_settled = account
+            .capture_hold("h-1", 10, DateTime::default())
+            .unwrap_err();
+
+        assert_eq!(exceeds_hold, OperationError::CaptureExceedsHold);
+        assert_eq!(not_found, OperationError::HoldNotFound);
+        assert_eq!(already_settled, OperationError::HoldAlreadySettled);
+        assert_eq!(account.available_limit, Money::from(100));
+    }
+
+    #[test]
+    fn expire_holds() {
+        let hold_time = DateTime::parse_from_rfc3339("2019-02-13T10:00:00.000Z")
+            .unwrap()
+            .into();
+        let mut account = Account::new(100, true, vec![]);
+        account
+            .place_hold(Hold::new("h-1", 60, "Hotel", hold_time))
+            .unwrap();
 
-        let (initial_tx, last_tx) = account.get_last_n_txs(3);
+        account.expire_holds(
+            DateTime::parse_from_rfc3339("2019-02-13T10:01:00.000Z")
+                .unwrap()
+                .into(),
+            60,
+        );
+        assert_eq!(account.available_limit, Money::from(40));
 
-        assert_eq!(initial_tx, &TX::new(50, "Merchant Y", DateTime::default()));
-        assert_eq!(last_tx, &TX::new(200, "Merchant ZZ", DateTime::default()));
+        account.expire_holds(
+            DateTime::parse_from_rfc3339("2019-02-13T10:01:01.000Z")
+                .unwrap()
+                .into(),
+            60,
+        );
+        assert_eq!(account.available_limit, Money::from(100));
+        assert_eq!(
+            account.capture_hold("h-1", 60, hold_time).unwrap_err(),
+            OperationError::HoldExpired
+        );
     }
 
     #[test]
@@ -224,6 +1015,57 @@ mod account {
         assert_eq!(inactive_account.is_inactive(), true);
         assert_eq!(active_account.is_inactive(), false);
     }
+
+    #[test]
+    fn execute_traced_tx() {
+        let mut account =
+            Account::new(100, true, vec![]).with_shadow_rules(vec![Arc::new(InsufficientLimit {})]);
+
+        let (result, trace) =
+            account.execute_traced_tx(TX::new(80, "Nike", DateTime::default()), false);
+
+        assert_eq!(result, Ok(AccountState::new(true, 20, vec![])));
+        assert_eq!(trace.len(), account.rules.len() + 1);
+        assert!(trace.iter().all(|t| t.ran() && t.evidence().is_none()));
+
+        let (result, trace) =
+            account.execute_traced_tx(TX::new(30, "Nike", DateTime::default()), true);
+
+        assert_eq!(result, Err(vec![OperationError::InsufficientLimit.into()]));
+        assert_eq!(
+            trace
+                .iter()
+                .filter_map(|t| t.violation())
+                .collect::<Vec<_>>(),
+            vec![
+                &Violation::from(OperationError::InsufficientLimit),
+                &Violation::from(OperationError::InsufficientLimit),
+            ]
+        );
+        assert!(trace.last().unwrap().is_shadow());
+        assert!(trace.iter().any(|t| t.evidence().is_some()));
+    }
+
+    #[test]
+    fn execute_tx_on_inactive_card() {
+        let mut account = Account::new(100, false, vec![]);
+
+        let result = account.execute_tx(TX::new(10, "Nike", DateTime::default()));
+
+        assert_eq!(result, Err(vec![OperationError::InactiveCard.into()]));
+        assert_eq!(account.available_limit, Money::from(100));
+    }
+
+    #[test]
+    fn trace_tx_on_inactive_card() {
+        let account = Account::new(100, false, vec![])
+            .with_shadow_rules(vec![Arc::new(InsufficientLimit {})]);
+
+        let trace = account.trace_tx(&TX::new(10, "Nike", DateTime::default()), true);
+
+        assert_eq!(trace.len(), account.rules.len() + 1);
+        assert!(trace.iter().all(|t| !t.ran() && t.violation().is_none()));
+    }
 }
 
 #[cfg(test)]
@@ -240,9 +1082,13 @@ mod account_state {
         let state = AccountState::new(true, 123, vec![]);
 
         let expected_state = AccountState {
+            account_id: String::new(),
             active_card: true,
-            available_limit: 123,
+            available_limit: Money::from(123),
             violations: vec![],
+            converted_amount: None,
+            shadow_violations: vec![],
+            trace: vec![],
         };
 
         assert_eq!(state, expected_state);
@@ -253,9 +1099,30 @@ mod account_state {
         let state = AccountState::new(true, 123, vec![OperationError::DuplicatedTx]);
 
         let expected_state = AccountState {
+            account_id: String::new(),
+            active_card: true,
+            available_limit: Money::from(123),
+            violations: vec![Violation::new("duplicated-tx")],
+            converted_amount: None,
+            shadow_violations: vec![],
+            trace: vec![],
+        };
+
+        assert_eq!(state, expected_state);
+    }
+
+    #[test]
+    fn create_with_account_id() {
+        let state = AccountState::new(true, 123, vec![]).with_account_id("a");
+
+        let expected_state = AccountState {
+            account_id: String::from("a"),
             active_card: true,
-            available_limit: 123,
-            violations: vec![String::from("duplicated-tx")],
+            available_limit: Money::from(123),
+            violations: vec![],
+            converted_amount: None,
+            shadow_violations: vec![],
+            trace: vec![],
         };
 
         assert_eq!(state, expected_state);
@@ -266,9 +1133,13 @@ mod account_state {
         let state = AccountState::not_initialized();
 
         let expected_state = AccountState {
+            account_id: String::new(),
             active_card: false,
-            available_limit: 0,
-            violations: vec![String::from("account-not-initialized")],
+            available_limit: Money::from(0),
+            violations: vec![Violation::new("account-not-initialized")],
+            converted_amount: None,
+            shadow_violations: vec![],
+            trace: vec![],
         };
 
         assert_eq!(state, expected_state);
@@ -279,13 +1150,32 @@ mod account_state {
         let state = AccountState::inactive(100);
 
         let expected_state = AccountState {
+            account_id: String::new(),
             active_card: false,
-            available_limit: 100,
-            violations: vec![String::from("inactive-card")],
+            available_limit: Money::from(100),
+            violations: vec![Violation::new("inactive-card")],
+            converted_amount: None,
+            shadow_violations: vec![],
+            trace: vec![],
         };
 
         assert_eq!(state, expected_state);
     }
+
+    #[test]
+    fn serialize_amounts_with_currency() {
+        let eur = Currency::from_code("EUR").unwrap();
+        let state = AccountState::new(true, Money::new(7778, eur), vec![])
+            .with_converted_amount(Money::new(2222, eur));
+
+        let json = serde_json::to_string(&state).unwrap();
+
+        assert_eq!(
+            json,
+            "{\"active_card\":true,\"available_limit\":\"77.78 EUR\",\"violations\":[],\"converted_amount\":\"22.22 EUR\"}"
+        );
+        assert_eq!(serde_json::from_str::<AccountState>(&json).unwrap(), state);
+    }
 }
 
 #[cfg(test)]
@@ -303,9 +1193,12 @@ mod tx {
         let tx = TX::new(100, "Merchant X", datetime);
 
         let expected_tx = TX {
-            amount: 100,
+            id: None,
+            amount: Money::from(100),
             merchant: String::from("Merchant X"),
             time: datetime,
+            mcc: None,
+            reversed: false,
         };
 
         assert_eq!(tx, expected_tx);
@@ -0,0 +1,531 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::account::OperationError;
+
+/// ISO 4217 codes supported by the authorizer and their number of minor units.
+const CURRENCIES: &[(&str, u32)] = &[
+    ("ARS", 2),
+    ("AUD", 2),
+    ("BHD", 3),
+    ("BRL", 2),
+    ("CAD", 2),
+    ("CHF", 2),
+    ("CLP", 0),
+    ("CNY", 2),
+    ("COP", 2),
+    ("EUR", 2),
+    ("GBP", 2),
+    ("INR", 2),
+    ("JOD", 3),
+    ("JPY", 0),
+    ("KRW", 0),
+    ("KWD", 3),
+    ("MXN", 2),
+    ("USD", 2),
+    ("UYU", 2),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Currency {
+    code: &'static str,
+    exponent: u32,
+}
+
+impl Currency {
+    pub const USD: Currency = Currency {
+        code: "USD",
+        exponent: 2,
+    };
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        CURRENCIES
+            .iter()
+            .find(|(c, _)| c.eq_ignore_ascii_case(code))
+            .map(|&(code, exponent)| Self { code, exponent })
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Currency::USD
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code)
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code)
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+
+        Currency::from_code(&code)
+            .ok_or_else(|| de::Error::custom(format!("unknown currency code '{}'", code)))
+    }
+}
+
+/// Exact non-negative decimal number, as received in the input operations.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Decimal {
+    digits: u64,
+    scale: u32,
+}
+
+impl Decimal {
+    pub fn new(digits: u64, scale: u32) -> Self {
+        let mut decimal = Self { digits, scale };
+
+        while decimal.scale > 0 && decimal.digits.is_multiple_of(10) {
+            decimal.digits /= 10;
+            decimal.scale -= 1;
+        }
+
+        decimal
+    }
+
+    /// Integer part and fraction digits, the latter padded to the scale. Scales too large for
+    /// a power of ten to fit in `u64` leave every digit in the fraction.
+    fn parts(&self) -> (u64, String) {
+        if self.scale == 0 {
+            return (self.digits, String::new());
+        }
+
+        let (integer, fraction) = match 10u64.checked_pow(self.scale) {
+            Some(factor) => (self.digits / factor, self.digits % factor),
+            None => (0, self.digits),
+        };
+
+        (
+            integer,
+            format!("{:0width$}", fraction, width = self.scale as usize),
+        )
+    }
+}
+
+impl From<u32> for Decimal {
+    fn from(value: u32) -> Self {
+        Decimal::new(value.into(), 0)
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    /// Compares the integer parts first and then the fraction digits, which order as text
+    /// once their trailing zeros are dropped, so no scale can overflow the comparison.
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (integer, fraction) = self.parts();
+        let (other_integer, other_fraction) = other.parts();
+
+        integer.cmp(&other_integer).then_with(|| {
+            fraction
+                .trim_end_matches('0')
+                .cmp(other_fraction.trim_end_matches('0'))
+        })
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid decimal amount '{}'", s);
+
+        let (integer, fraction) = s.split_once('.').unwrap_or((s, ""));
+
+        if integer.is_empty()
+            || !(integer.chars().chain(fraction.chars())).all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let digits = format!("{}{}", integer, fraction)
+            .parse::<u64>()
+            .map_err(|_| invalid())?;
+
+        Ok(Decimal::new(digits, fraction.len() as u32))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (integer, fraction) = self.parts();
+
+        if fraction.is_empty() {
+            write!(f, "{}", integer)
+        } else {
+            write!(f, "{}.{}", integer, fraction)
+        }
+    }
+}
+
+/// Decimals are stored as strings, as floats can't hold every exact amount. Amounts are still
+/// read from json numbers, as written in the input operations.
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DecimalVisitor;
+
+        impl Visitor<'_> for DecimalVisitor {
+            type Value = Decimal;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a non-negative decimal amount")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Decimal, E> {
+                Ok(Decimal::new(value, 0))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Decimal, E> {
+                u64::try_from(value)
+                    .map(|value| Decimal::new(value, 0))
+                    .map_err(|_| E::custom(format!("negative amount {}", value)))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Decimal, E> {
+                // The shortest representation of the float is the decimal written in the input.
+                value.to_string().parse().map_err(E::custom)
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Decimal, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(DecimalVisitor)
+    }
+}
+
+/// Amount of a currency, stored in its minor units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Money {
+    minor_units: u64,
+    currency: Currency,
+}
+
+impl Money {
+    pub fn new(minor_units: u64, currency: Currency) -> Self {
+        Self {
+            minor_units,
+            currency,
+        }
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Self::new(0, currency)
+    }
+
+    /// Converts a decimal amount to the currency minor units, rejecting amounts with more
+    /// decimal places than the currency has.
+    pub fn from_decimal(amount: Decimal, currency: Currency) -> Result<Self, OperationError> {
+        if amount.scale > currency.exponent {
+            return Err(OperationError::InvalidAmount);
+        }
+
+        10u64
+            .checked_pow(currency.exponent - amount.scale)
+            .and_then(|factor| amount.digits.checked_mul(factor))
+            .map(|minor_units| Self::new(minor_units, currency))
+            .ok_or(OperationError::InvalidAmount)
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn to_decimal(self) -> Decimal {
+        Decimal::new(self.minor_units, self.currency.exponent)
+    }
+
+    /// Converts to `currency` at `rate`, charging `markup_percent` on top and rounding half up to
+    /// the target minor unit. Returns `None` if the converted amount doesn't fit.
+    pub fn convert(
+        self,
+        currency: Currency,
+        rate: Decimal,
+        markup_percent: Decimal,
+    ) -> Option<Money> {
+        let pow10 = |exponent: u32| 10u128.checked_pow(exponent);
+
+        let markup_factor = pow10(markup_percent.scale)?
+            .checked_mul(100)?
+            .checked_add(markup_percent.digits.into())?;
+
+        let numerator = u128::from(self.minor_units)
+            .checked_mul(rate.digits.into())?
+            .checked_mul(pow10(currency.exponent)?)?
+            .checked_mul(markup_factor)?;
+        let denominator = pow10(rate.scale)?
+            .checked_mul(pow10(self.currency.exponent)?)?
+            .checked_mul(pow10(markup_percent.scale)?)?
+            .checked_mul(100)?;
+
+        let minor_units = numerator.checked_add(denominator / 2)? / denominator;
+
+        u64::try_from(minor_units)
+            .ok()
+            .map(|minor_units| Money::new(minor_units, currency))
+    }
+
+    pub fn saturating_add(self, other: Money) -> Money {
+        debug_assert_eq!(self.currency, other.currency);
+        Money::new(
+            self.minor_units.saturating_add(other.minor_units),
+            self.currency,
+        )
+    }
+
+    pub fn saturating_sub(self, other: Money) -> Money {
+        debug_assert_eq!(self.currency, other.currency);
+        Money::new(
+            self.minor_units.saturating_sub(other.minor_units),
+            self.currency,
+        )
+    }
+}
+
+/// Whole units of the default currency.
+impl From<u32> for Money {
+    fn from(units: u32) -> Self {
+        Money::from_decimal(Decimal::from(units), Currency::default()).unwrap()
+    }
+}
+
+impl PartialOrd for Money {
+    /// Amounts of different currencies can't be compared.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.currency != other.currency {
+            return None;
+        }
+
+        Some(self.minor_units.cmp(&other.minor_units))
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let factor = 10u64.pow(self.currency.exponent);
+
+        if self.currency.exponent == 0 {
+            write!(f, "{} {}", self.minor_units, self.currency)
+        } else {
+            write!(
+                f,
+                "{}.{:0width$} {}",
+                self.minor_units / factor,
+                self.minor_units % factor,
+                self.currency,
+                width = self.currency.exponent as usize
+            )
+        }
+    }
+}
+
+impl FromStr for Money {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (amount, code) = s
+            .trim()
+            .split_once(' ')
+            .unwrap_or((s.trim(), Currency::default().code()));
+
+        let currency =
+            Currency::from_code(code).ok_or_else(|| format!("unknown currency code '{}'", code))?;
+        let amount: Decimal = amount.parse()?;
+
+        Money::from_decimal(amount, currency)
+            .map_err(|_| format!("invalid {} amount '{}'", currency, amount))
+    }
+}
+
+/// Money is stored as `"<amount> <currency>"`, while bare numbers are read as amounts of the
+/// default currency.
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum MoneyInput {
+            Text(String),
+            Amount(Decimal),
+        }
+
+        match MoneyInput::deserialize(deserializer)? {
+            MoneyInput::Text(text) => text.parse().map_err(de::Error::custom),
+            MoneyInput::Amount(amount) => Money::from_decimal(amount, Currency::default())
+                .map_err(|_| de::Error::custom(format!("invalid amount {}", amount))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod money_amounts {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn eur() -> Currency {
+        Currency::from_code("EUR").unwrap()
+    }
+
+    #[test]
+    fn find_currency_by_code() {
+        assert_eq!(Currency::from_code("usd"), Some(Currency::USD));
+        assert_eq!(Currency::from_code("JPY").unwrap().exponent, 0);
+        assert_eq!(Currency::from_code("KWD").unwrap().exponent, 3);
+        assert_eq!(Currency::from_code("XXX"), None);
+    }
+
+    #[test]
+    fn parse_decimal() {
+        assert_eq!("20".parse(), Ok(Decimal::new(20, 0)));
+        assert_eq!("20.50".parse(), Ok(Decimal::new(205, 1)));
+        assert_eq!("0.05".parse(), Ok(Decimal::new(5, 2)));
+        assert!("-1".parse::<Decimal>().is_err());
+        assert!(".5".parse::<Decimal>().is_err());
+        assert!("1e3".parse::<Decimal>().is_err());
+    }
+
+    #[test]
+    fn compare_decimal() {
+        assert!(Decimal::new(205, 1) > Decimal::new(20, 0));
+        assert!(Decimal::new(2049, 2) < Decimal::new(205, 1));
+        assert_eq!(
+            Decimal::new(2050, 2).cmp(&Decimal::new(205, 1)),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn compare_decimal_with_distant_scales() {
+        assert!(Decimal::new(1, 30) < Decimal::new(1, 0));
+        assert!(Decimal::new(1, 30) > Decimal::new(1, 31));
+        assert!(Decimal::new(u64::MAX, 25) < Decimal::new(2, 5));
+        assert!(Decimal::new(u64::MAX, 0) > Decimal::new(u64::MAX, 19));
+    }
+
+    #[test]
+    fn format_decimal() {
+        assert_eq!(Decimal::new(20, 0).to_string(), "20");
+        assert_eq!(Decimal::new(5, 2).to_string(), "0.05");
+        assert_eq!(
+            Decimal::new(u64::MAX, 19).to_string(),
+            "1.8446744073709551615"
+        );
+        assert_eq!(Decimal::new(12, 21).to_string(), "0.000000000000000000012");
+    }
+
+    #[test]
+    fn deserialize_decimal() {
+        let amounts: Vec<Decimal> = serde_json::from_str("[20, 20.5, 0.1, \"12.34\"]").unwrap();
+
+        assert_eq!(
+            amounts,
+            vec![
+                Decimal::new(20, 0),
+                Decimal::new(205, 1),
+                Decimal::new(1, 1),
+                Decimal::new(1234, 2),
+            ]
+        );
+        assert!(serde_json::from_str::<Decimal>("-20").is_err());
+    }
+
+    #[test]
+    fn serialize_decimal() {
+        let json =
+            serde_json::to_string(&vec![Decimal::new(20, 0), Decimal::new(1234, 2)]).unwrap();
+
+        assert_eq!(json, "[\"20\",\"12.34\"]");
+        assert_eq!(
+            serde_json::from_str::<Vec<Decimal>>(&json).unwrap(),
+            vec![Decimal::new(20, 0), Decimal::new(1234, 2)]
+        );
+    }
+
+    #[test]
+    fn convert_decimal_to_minor_units() {
+        assert_eq!(
+            Money::from_decimal(Decimal::new(2050, 2), Currency::USD),
+            Ok(Money::new(2050, Currency::USD))
+        );
+        assert_eq!(
+            Money::from_decimal(Decimal::new(20, 0), Currency::from_code("KWD").unwrap()),
+            Ok(Money::new(20000, Currency::from_code("KWD").unwrap()))
+        );
+        assert_eq!(
+            Money::from_decimal(Decimal::new(20555, 3), Currency::USD),
+            Err(OperationError::InvalidAmount)
+        );
+        assert_eq!(
+            Money::from_decimal(Decimal::new(5, 1), Currency::from_code("JPY").unwrap()),
+            Err(OperationError::InvalidAmount)
+        );
+    }
+
+    #[test]
+    fn compare_money() {
+        assert!(Money::from(10) < Money::from(20));
+        assert_eq!(
+            Money::new(10, Currency::USD).partial_cmp(&Money::new(10, eur())),
+            None
+        );
+    }
+
+    #[test]
+    fn format_money() {
+        assert_eq!(Money::new(2050, Currency::USD).to_string(), "20.50 USD");
+        assert_eq!(Money::new(5, eur()).to_string(), "0.05 EUR");
+        assert_eq!(
+            Money::new(1500, Currency::from_code("JPY").unwrap()).to_string(),
+            "1500 JPY"
+        );
+    }
+
+    #[test]
+    fn serialize_money() {
+        let money = Money::new(2050, eur());
+
+        let json = serde_json::to_string(&money).unwrap();
+
+        assert_eq!(json, "\"20.50 EUR\"");
+        assert_eq!(serde_json::from_str::<Money>(&json).unwrap(), money);
+        assert_eq!(
+            serde_json::from_str::<Money>("80").unwrap(),
+            Money::from(80)
+        );
+    }
+}
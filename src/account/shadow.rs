@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::account::Violation;
+
+/// Violations reported by shadow rules across every transaction they were checked against,
+/// none of which declined a transaction.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ShadowSummary {
+    checked_txs: usize,
+    /// Transactions with at least one shadow violation.
+    flagged_txs: usize,
+    /// Authorized transactions the shadow rules would have declined.
+    would_decline: usize,
+    /// Number of times each violation code was reported.
+    violations: BTreeMap<String, usize>,
+}
+
+impl ShadowSummary {
+    /// Adds the shadow violations of a transaction, authorized or not by the active rules.
+    pub fn record(&mut self, shadow_violations: &[Violation], authorized: bool) {
+        self.checked_txs += 1;
+
+        if shadow_violations.is_empty() {
+            return;
+        }
+
+        self.flagged_txs += 1;
+
+        if authorized {
+            self.would_decline += 1;
+        }
+
+        for violation in shadow_violations {
+            *self
+                .violations
+                .entry(violation.code().to_string())
+                .or_default() += 1;
+        }
+    }
+
+    pub fn checked_txs(&self) -> usize {
+        self.checked_txs
+    }
+
+    pub fn flagged_txs(&self) -> usize {
+        self.flagged_txs
+    }
+
+    pub fn would_decline(&self) -> usize {
+        self.would_decline
+    }
+
+    pub fn violations(&self) -> &BTreeMap<String, usize> {
+        &self.violations
+    }
+}
+
+#[cfg(test)]
+mod shadow_summary {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn record_violations() {
+        let mut summary = ShadowSummary::default();
+
+        summary.record(&[], true);
+        summary.record(&[Violation::new("merchant-velocity")], true);
+        summary.record(
+            &[
+                Violation::new("merchant-velocity"),
+                Violation::new("large-purchase"),
+            ],
+            false,
+        );
+
+        assert_eq!(summary.checked_txs(), 3);
+        assert_eq!(summary.flagged_txs(), 2);
+        assert_eq!(summary.would_decline(), 1);
+        assert_eq!(
+            summary.violations(),
+            &BTreeMap::from([
+                (String::from("large-purchase"), 1),
+                (String::from("merchant-velocity"), 2),
+            ])
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::account::Violation;
+
+/// What a rule decided for a transaction in explain mode, and the facts it based it on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RuleTrace {
+    rule: String,
+    ran: bool,
+    /// Verdict of the rule, `None` when it passed or didn't run.
+    violation: Option<Violation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    evidence: Option<Value>,
+    /// Whether the rule runs in shadow mode, its violation never declining the transaction.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    shadow: bool,
+}
+
+impl RuleTrace {
+    pub fn new(rule: &str, violation: Option<Violation>) -> Self {
+        Self {
+            rule: rule.to_string(),
+            ran: true,
+            violation,
+            evidence: None,
+            shadow: false,
+        }
+    }
+
+    /// Trace of a rule skipped because the transaction was rejected before rules were checked.
+    pub fn not_run(rule: &str) -> Self {
+        Self {
+            ran: false,
+            ..Self::new(rule, None)
+        }
+    }
+
+    pub fn with_evidence(mut self, evidence: Value) -> Self {
+        self.evidence = Some(evidence);
+        self
+    }
+
+    pub fn into_shadow(mut self) -> Self {
+        self.shadow = true;
+        self
+    }
+
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+
+    pub fn ran(&self) -> bool {
+        self.ran
+    }
+
+    pub fn violation(&self) -> Option<&Violation> {
+        self.violation.as_ref()
+    }
+
+    pub fn evidence(&self) -> Option<&Value> {
+        self.evidence.as_ref()
+    }
+
+    pub fn is_shadow(&self) -> bool {
+        self.shadow
+    }
+}
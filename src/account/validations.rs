@@ -1,33 +1,85 @@
 use std::fmt::Debug;
 
-use crate::account::errors::OperationError;
-use crate::account::models::{Account, TX};
+use chrono::Datelike;
+use chrono_tz::Tz;
+use serde_json::{json, Value};
 
-pub trait AccountRule: Debug {
-    fn validate(&self, account: &Account, tx: &TX) -> Option<OperationError>;
+use crate::account::errors::{OperationError, Violation};
+use crate::account::models::{Account, HoldStatus, TX};
+use crate::account::money::Money;
+use crate::account::trace::RuleTrace;
+
+pub trait AccountRule: Debug + Send + Sync {
+    fn validate(&self, account: &Account, tx: &TX) -> Option<Violation>;
+
+    /// Name the rule is reported with in explain mode, its type name without path or generic
+    /// arguments by default.
+    fn name(&self) -> &str {
+        let type_name = std::any::type_name::<Self>();
+        let type_name = type_name.split('<').next().unwrap_or(type_name);
+
+        type_name.rsplit("::").next().unwrap_or(type_name)
+    }
+
+    /// Validates the transaction, recording the evidence the verdict was based on.
+    fn explain(&self, account: &Account, tx: &TX) -> RuleTrace {
+        RuleTrace::new(self.name(), self.validate(account, tx))
+    }
+}
+
+fn tx_evidence(tx: &TX) -> Value {
+    json!({
+        "merchant": tx.merchant,
+        "amount": tx.amount.to_decimal(),
+        "time": tx.time,
+    })
 }
 
+/// Rejects a transaction when an authorized one with the same amount and merchant, which wasn't
+/// refunded, happened within `window_seconds` of it.
 #[derive(Debug)]
-pub struct DuplicatedTx {}
+pub struct DuplicatedTx {
+    window_seconds: i64,
+}
 
 impl DuplicatedTx {
-    pub fn boxed() -> Box<Self> {
-        Box::new(Self {})
+    pub const DEFAULT_WINDOW_SECONDS: i64 = 120;
+
+    pub fn new(window_seconds: i64) -> Self {
+        Self { window_seconds }
+    }
+
+    pub fn boxed(window_seconds: i64) -> Box<Self> {
+        Box::new(Self::new(window_seconds))
+    }
+
+    fn find_duplicate<'a>(&self, account: &'a Account, tx: &TX) -> Option<&'a TX> {
+        account.txs.iter().find(|t| {
+            !t.reversed
+                && t.amount == tx.amount
+                && t.merchant == tx.merchant
+                && tx.seconds_since(t).abs() <= self.window_seconds
+        })
     }
 }
 
 impl AccountRule for DuplicatedTx {
-    fn validate(&self, account: &Account, tx: &TX) -> Option<OperationError> {
-        let duplicated_tx = account
-            .txs
-            .iter()
-            .any(|t| t.amount == tx.amount && t.merchant == tx.merchant);
+    fn validate(&self, account: &Account, tx: &TX) -> Option<Violation> {
+        self.find_duplicate(account, tx)
+            .map(|_| OperationError::DuplicatedTx.into())
+    }
 
-        if duplicated_tx {
-            return Some(OperationError::DuplicatedTx);
-        }
+    fn name(&self) -> &str {
+        "duplicated-tx"
+    }
 
-        None
+    fn explain(&self, account: &Account, tx: &TX) -> RuleTrace {
+        let duplicate = self.find_duplicate(account, tx);
+
+        RuleTrace::new(self.name(), self.validate(account, tx)).with_evidence(json!({
+            "window-seconds": self.window_seconds,
+            "duplicate": duplicate.map(tx_evidence),
+        }))
     }
 }
 
@@ -41,47 +93,356 @@ impl InsufficientLimit {
 }
 
 impl AccountRule for InsufficientLimit {
-    fn validate(&self, account: &Account, tx: &TX) -> Option<OperationError> {
+    fn validate(&self, account: &Account, tx: &TX) -> Option<Violation> {
         if account.available_limit < tx.amount {
-            return Some(OperationError::InsufficientLimit);
+            return Some(OperationError::InsufficientLimit.into());
         }
 
         None
     }
+
+    fn name(&self) -> &str {
+        "insufficient-limit"
+    }
+
+    fn explain(&self, account: &Account, tx: &TX) -> RuleTrace {
+        RuleTrace::new(self.name(), self.validate(account, tx)).with_evidence(json!({
+            "available-limit": account.available_limit.to_decimal(),
+            "amount": tx.amount.to_decimal(),
+        }))
+    }
 }
 
+/// Rejects a transaction coming within `window_seconds` of the last of `max_txs` authorized
+/// transactions, not counting refunded ones, which all happened within `window_seconds`.
 #[derive(Debug)]
-pub struct HighFrequencySmallInterval {}
+pub struct HighFrequencySmallInterval {
+    max_txs: usize,
+    window_seconds: i64,
+}
 
 impl HighFrequencySmallInterval {
+    pub const DEFAULT_MAX_TXS: usize = 3;
+    pub const DEFAULT_WINDOW_SECONDS: i64 = 120;
+
+    pub fn new(max_txs: usize, window_seconds: i64) -> Self {
+        Self {
+            max_txs,
+            window_seconds,
+        }
+    }
+
+    pub fn boxed(max_txs: usize, window_seconds: i64) -> Box<Self> {
+        Box::new(Self::new(max_txs, window_seconds))
+    }
+
+    /// The last `max_txs` authorized transactions that weren't refunded, oldest first.
+    fn recent_txs<'a>(&self, account: &'a Account) -> Vec<&'a TX> {
+        let mut recent_txs: Vec<_> = account
+            .txs
+            .iter()
+            .rev()
+            .filter(|t| !t.reversed)
+            .take(self.max_txs)
+            .collect();
+        recent_txs.reverse();
+
+        recent_txs
+    }
+}
+
+impl AccountRule for HighFrequencySmallInterval {
+    fn validate(&self, account: &Account, tx: &TX) -> Option<Violation> {
+        if self.max_txs == 0 {
+            return Some(OperationError::HighFrequencySmallInterval.into());
+        }
+
+        let recent_txs = self.recent_txs(account);
+
+        let (Some(first_tx), Some(last_tx)) = (recent_txs.first(), recent_txs.last()) else {
+            return None;
+        };
+
+        if recent_txs.len() == self.max_txs
+            && tx.seconds_since(last_tx) <= self.window_seconds
+            && last_tx.seconds_since(first_tx) <= self.window_seconds
+        {
+            return Some(OperationError::HighFrequencySmallInterval.into());
+        }
+
+        None
+    }
+
+    fn name(&self) -> &str {
+        "high-frequency-small-interval"
+    }
+
+    /// Records the times of the last `max_txs` authorized transactions and the new one.
+    fn explain(&self, account: &Account, tx: &TX) -> RuleTrace {
+        let times: Vec<_> = self
+            .recent_txs(account)
+            .into_iter()
+            .chain([tx])
+            .map(|t| t.time)
+            .collect();
+
+        RuleTrace::new(self.name(), self.validate(account, tx)).with_evidence(json!({
+            "max-txs": self.max_txs,
+            "window-seconds": self.window_seconds,
+            "times": times,
+        }))
+    }
+}
+
+/// Rejects a transaction whose merchant category code falls in a category blocked for the
+/// account. Transactions without a code are never blocked.
+#[derive(Debug)]
+pub struct BlockedMerchantCategory {}
+
+impl BlockedMerchantCategory {
     pub fn boxed() -> Box<Self> {
         Box::new(Self {})
     }
 }
 
-impl AccountRule for HighFrequencySmallInterval {
-    fn validate(&self, account: &Account, tx: &TX) -> Option<OperationError> {
-        let total_txs = account.txs.len();
+impl AccountRule for BlockedMerchantCategory {
+    fn validate(&self, account: &Account, tx: &TX) -> Option<Violation> {
+        let mcc = tx.mcc?;
+
+        if account.blocked_categories.iter().any(|c| c.contains(mcc)) {
+            return Some(OperationError::BlockedMerchantCategory.into());
+        }
 
-        if total_txs >= 3 {
-            let (left_tx, right_tx) = account.get_last_n_txs(3);
+        None
+    }
 
-            if (tx.seconds_since(right_tx)) <= 120 && right_tx.seconds_since(left_tx) <= 120 {
-                return Some(OperationError::HighFrequencySmallInterval);
-            }
+    fn name(&self) -> &str {
+        "blocked-merchant-category"
+    }
+
+    fn explain(&self, account: &Account, tx: &TX) -> RuleTrace {
+        RuleTrace::new(self.name(), self.validate(account, tx)).with_evidence(json!({
+            "mcc": tx.mcc,
+            "blocked-categories": account.blocked_categories,
+        }))
+    }
+}
+
+/// Rejects a transaction that would take the spend of its calendar day, in `timezone`, over
+/// the account daily limit. Active holds count as spent.
+#[derive(Debug)]
+pub struct DailyLimit {
+    timezone: Tz,
+}
+
+impl DailyLimit {
+    pub fn new(timezone: Tz) -> Self {
+        Self { timezone }
+    }
+
+    pub fn boxed(timezone: Tz) -> Box<Self> {
+        Box::new(Self::new(timezone))
+    }
+
+    /// Spend of the transaction calendar day, including it and the active holds.
+    fn spent(&self, account: &Account, tx: &TX) -> Money {
+        let day = tx.time.with_timezone(&self.timezone).date_naive();
+        let same_day = |t: &TX| t.time.with_timezone(&self.timezone).date_naive() == day;
+
+        account
+            .spent_where(same_day)
+            .saturating_add(account.held_where(same_day))
+            .saturating_add(tx.amount)
+    }
+}
+
+impl AccountRule for DailyLimit {
+    fn validate(&self, account: &Account, tx: &TX) -> Option<Violation> {
+        let daily_limit = account.daily_limit?;
+
+        if self.spent(account, tx) > daily_limit {
+            return Some(OperationError::DailyLimitExceeded.into());
+        }
+
+        None
+    }
+
+    fn name(&self) -> &str {
+        "daily-limit"
+    }
+
+    fn explain(&self, account: &Account, tx: &TX) -> RuleTrace {
+        RuleTrace::new(self.name(), self.validate(account, tx)).with_evidence(json!({
+            "daily-limit": account.daily_limit.map(Money::to_decimal),
+            "spent": self.spent(account, tx).to_decimal(),
+            "day": tx.time.with_timezone(&self.timezone).date_naive(),
+            "timezone": self.timezone,
+        }))
+    }
+}
+
+/// Rejects a transaction that would take the spend of its calendar month, in `timezone`, over
+/// the account monthly limit. Active holds count as spent.
+#[derive(Debug)]
+pub struct MonthlyLimit {
+    timezone: Tz,
+}
+
+impl MonthlyLimit {
+    pub fn new(timezone: Tz) -> Self {
+        Self { timezone }
+    }
+
+    pub fn boxed(timezone: Tz) -> Box<Self> {
+        Box::new(Self::new(timezone))
+    }
+
+    /// Spend of the transaction calendar month, including it and the active holds.
+    fn spent(&self, account: &Account, tx: &TX) -> Money {
+        let time = tx.time.with_timezone(&self.timezone);
+        let same_month = |t: &TX| {
+            let t_time = t.time.with_timezone(&self.timezone);
+            t_time.year() == time.year() && t_time.month() == time.month()
+        };
+
+        account
+            .spent_where(same_month)
+            .saturating_add(account.held_where(same_month))
+            .saturating_add(tx.amount)
+    }
+}
+
+impl AccountRule for MonthlyLimit {
+    fn validate(&self, account: &Account, tx: &TX) -> Option<Violation> {
+        let monthly_limit = account.monthly_limit?;
+
+        if self.spent(account, tx) > monthly_limit {
+            return Some(OperationError::MonthlyLimitExceeded.into());
+        }
+
+        None
+    }
+
+    fn name(&self) -> &str {
+        "monthly-limit"
+    }
+
+    fn explain(&self, account: &Account, tx: &TX) -> RuleTrace {
+        RuleTrace::new(self.name(), self.validate(account, tx)).with_evidence(json!({
+            "monthly-limit": account.monthly_limit.map(Money::to_decimal),
+            "spent": self.spent(account, tx).to_decimal(),
+            "month": tx.time.with_timezone(&self.timezone).format("%Y-%m").to_string(),
+            "timezone": self.timezone,
+        }))
+    }
+}
+
+/// Rejects a transaction that would take the amount spent or the number of transactions at its
+/// merchant within `window_seconds` of it over `max_amount` or `max_txs`. The amount is only
+/// capped for accounts in the `max_amount` currency.
+#[derive(Debug)]
+pub struct MerchantLimit {
+    window_seconds: i64,
+    max_amount: Option<Money>,
+    max_txs: Option<usize>,
+}
+
+impl MerchantLimit {
+    pub const DEFAULT_WINDOW_SECONDS: i64 = 24 * 60 * 60;
+
+    pub fn new(window_seconds: i64, max_amount: Option<Money>, max_txs: Option<usize>) -> Self {
+        Self {
+            window_seconds,
+            max_amount,
+            max_txs,
+        }
+    }
+
+    pub fn boxed(
+        window_seconds: i64,
+        max_amount: Option<Money>,
+        max_txs: Option<usize>,
+    ) -> Box<Self> {
+        Box::new(Self::new(window_seconds, max_amount, max_txs))
+    }
+
+    fn at_merchant(&self, t: &TX, tx: &TX) -> bool {
+        t.merchant == tx.merchant && tx.seconds_since(t).abs() <= self.window_seconds
+    }
+
+    /// Amount spent at the transaction merchant within the window, including it and the active
+    /// holds.
+    fn spent(&self, account: &Account, tx: &TX) -> Money {
+        let at_merchant = |t: &TX| self.at_merchant(t, tx);
+
+        account
+            .spent_where(at_merchant)
+            .saturating_add(account.held_where(at_merchant))
+            .saturating_add(tx.amount)
+    }
+
+    /// Transactions at the transaction merchant within the window, including it and the active
+    /// holds.
+    fn txs(&self, account: &Account, tx: &TX) -> usize {
+        let txs = account
+            .txs
+            .iter()
+            .filter(|t| !t.reversed && self.at_merchant(t, tx))
+            .count();
+
+        let holds = account
+            .holds
+            .iter()
+            .filter(|h| h.status == HoldStatus::Active && self.at_merchant(&h.to_tx(), tx))
+            .count();
+
+        txs + holds + 1
+    }
+}
+
+impl AccountRule for MerchantLimit {
+    fn validate(&self, account: &Account, tx: &TX) -> Option<Violation> {
+        let exceeds_amount = self
+            .max_amount
+            .filter(|max_amount| max_amount.currency() == account.currency())
+            .is_some_and(|max_amount| self.spent(account, tx) > max_amount);
+
+        let exceeds_txs = self
+            .max_txs
+            .is_some_and(|max_txs| self.txs(account, tx) > max_txs);
+
+        if exceeds_amount || exceeds_txs {
+            return Some(OperationError::MerchantLimitExceeded.into());
         }
 
         None
     }
+
+    fn name(&self) -> &str {
+        "merchant-limit"
+    }
+
+    fn explain(&self, account: &Account, tx: &TX) -> RuleTrace {
+        RuleTrace::new(self.name(), self.validate(account, tx)).with_evidence(json!({
+            "window-seconds": self.window_seconds,
+            "max-amount": self.max_amount.map(Money::to_decimal),
+            "max-txs": self.max_txs,
+            "spent": self.spent(account, tx).to_decimal(),
+            "txs": self.txs(account, tx),
+        }))
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod validations {
     use super::*;
 
     use chrono::DateTime;
     use pretty_assertions::assert_eq;
 
+    use crate::account::{Currency, Hold, MerchantCategory};
+
     #[test]
     fn insufficient_limit_tx_amount_valid() {
         let account = Account::new(100, true, vec![]);
@@ -97,7 +458,7 @@ mod validations {
 
         assert_eq!(
             InsufficientLimit {}.validate(&account, &tx),
-            Some(OperationError::InsufficientLimit)
+            Some(OperationError::InsufficientLimit.into())
         );
     }
 
@@ -106,7 +467,7 @@ mod validations {
         let account = Account::new(100, true, vec![TX::new(101, "Nike", DateTime::default())]);
         let tx = TX::new(102, "Nike", DateTime::default());
 
-        assert_eq!(DuplicatedTx {}.validate(&account, &tx), None);
+        assert_eq!(DuplicatedTx::new(120).validate(&account, &tx), None);
     }
 
     #[test]
@@ -115,9 +476,112 @@ mod validations {
         let tx = TX::new(101, "Nike", DateTime::default());
 
         assert_eq!(
-            DuplicatedTx {}.validate(&account, &tx),
-            Some(OperationError::DuplicatedTx)
+            DuplicatedTx::new(120).validate(&account, &tx),
+            Some(OperationError::DuplicatedTx.into())
+        );
+    }
+
+    #[test]
+    fn duplicated_tx_at_window_boundary() {
+        let account = Account::new(
+            1000,
+            true,
+            vec![TX::new(
+                101,
+                "Nike",
+                DateTime::parse_from_rfc3339("2019-02-13T11:00:00.000Z")
+                    .unwrap()
+                    .into(),
+            )],
+        );
+        let tx = TX::new(
+            101,
+            "Nike",
+            DateTime::parse_from_rfc3339("2019-02-13T11:02:00.000Z")
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(
+            DuplicatedTx::new(120).validate(&account, &tx),
+            Some(OperationError::DuplicatedTx.into())
+        );
+    }
+
+    #[test]
+    fn duplicated_tx_after_window() {
+        let account = Account::new(
+            1000,
+            true,
+            vec![TX::new(
+                101,
+                "Nike",
+                DateTime::parse_from_rfc3339("2019-02-13T11:00:00.000Z")
+                    .unwrap()
+                    .into(),
+            )],
+        );
+        let tx = TX::new(
+            101,
+            "Nike",
+            DateTime::parse_from_rfc3339("2019-02-13T11:02:01.000Z")
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(DuplicatedTx::new(120).validate(&account, &tx), None);
+    }
+
+    #[test]
+    fn duplicated_tx_days_apart() {
+        let account = Account::new(
+            1000,
+            true,
+            vec![TX::new(
+                101,
+                "Nike",
+                DateTime::parse_from_rfc3339("2019-02-13T11:00:00.000Z")
+                    .unwrap()
+                    .into(),
+            )],
+        );
+        let tx = TX::new(
+            101,
+            "Nike",
+            DateTime::parse_from_rfc3339("2019-02-16T11:00:00.000Z")
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(DuplicatedTx::new(120).validate(&account, &tx), None);
+    }
+
+    #[test]
+    fn duplicated_tx_custom_window() {
+        let account = Account::new(
+            1000,
+            true,
+            vec![TX::new(
+                101,
+                "Nike",
+                DateTime::parse_from_rfc3339("2019-02-13T11:00:00.000Z")
+                    .unwrap()
+                    .into(),
+            )],
+        );
+        let tx = TX::new(
+            101,
+            "Nike",
+            DateTime::parse_from_rfc3339("2019-02-13T11:05:00.000Z")
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(
+            DuplicatedTx::new(300).validate(&account, &tx),
+            Some(OperationError::DuplicatedTx.into())
         );
+        assert_eq!(DuplicatedTx::new(299).validate(&account, &tx), None);
     }
 
     #[test]
@@ -157,7 +621,10 @@ mod validations {
                 .into(),
         );
 
-        assert_eq!(HighFrequencySmallInterval {}.validate(&account, &tx), None);
+        assert_eq!(
+            HighFrequencySmallInterval::new(3, 120).validate(&account, &tx),
+            None
+        );
     }
 
     #[test]
@@ -198,8 +665,636 @@ mod validations {
         );
 
         assert_eq!(
-            HighFrequencySmallInterval {}.validate(&account, &tx),
-            Some(OperationError::HighFrequencySmallInterval)
+            HighFrequencySmallInterval::new(3, 120).validate(&account, &tx),
+            Some(OperationError::HighFrequencySmallInterval.into())
+        );
+    }
+
+    #[test]
+    fn high_frequency_small_interval_sliding_window() {
+        let time = |seconds: i64| {
+            DateTime::parse_from_rfc3339("2019-02-13T11:00:00.000Z")
+                .unwrap()
+                .with_timezone(&chrono::Utc)
+                + chrono::Duration::seconds(seconds)
+        };
+        let account = Account::new(
+            1000,
+            true,
+            vec![
+                TX::new(101, "Nike", time(0)),
+                TX::new(101, "Coke", time(60)),
+                TX::new(101, "Pepsi", time(120)),
+            ],
+        );
+
+        assert_eq!(
+            HighFrequencySmallInterval::new(3, 120)
+                .validate(&account, &TX::new(102, "RedBull", time(240))),
+            Some(OperationError::HighFrequencySmallInterval.into())
+        );
+        assert_eq!(
+            HighFrequencySmallInterval::new(3, 120)
+                .validate(&account, &TX::new(102, "RedBull", time(241))),
+            None
+        );
+    }
+
+    #[test]
+    fn high_frequency_small_interval_custom_limits() {
+        let account = Account::new(
+            1000,
+            true,
+            vec![
+                TX::new(
+                    101,
+                    "Nike",
+                    DateTime::parse_from_rfc3339("2019-02-13T11:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+                TX::new(
+                    101,
+                    "Coke",
+                    DateTime::parse_from_rfc3339("2019-02-13T11:00:30.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+            ],
+        );
+        let tx = TX::new(
+            102,
+            "RedBull",
+            DateTime::parse_from_rfc3339("2019-02-13T11:01:00.000Z")
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(
+            HighFrequencySmallInterval::new(2, 60).validate(&account, &tx),
+            Some(OperationError::HighFrequencySmallInterval.into())
+        );
+        assert_eq!(
+            HighFrequencySmallInterval::new(2, 29).validate(&account, &tx),
+            None
+        );
+        assert_eq!(
+            HighFrequencySmallInterval::new(3, 60).validate(&account, &tx),
+            None
         );
     }
+
+    #[test]
+    fn duplicated_tx_refunded_tx() {
+        let refunded_tx = TX {
+            reversed: true,
+            ..TX::new(101, "Nike", DateTime::default())
+        };
+        let account = Account::new(100, true, vec![refunded_tx]);
+        let tx = TX::new(101, "Nike", DateTime::default());
+
+        assert_eq!(DuplicatedTx::new(120).validate(&account, &tx), None);
+    }
+
+    #[test]
+    fn high_frequency_small_interval_refunded_txs() {
+        let account = Account::new(
+            1000,
+            true,
+            vec![
+                TX::new(
+                    101,
+                    "Nike",
+                    DateTime::parse_from_rfc3339("2019-02-13T11:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+                TX {
+                    reversed: true,
+                    ..TX::new(
+                        101,
+                        "Coke",
+                        DateTime::parse_from_rfc3339("2019-02-13T11:00:30.000Z")
+                            .unwrap()
+                            .into(),
+                    )
+                },
+            ],
+        );
+        let tx = TX::new(
+            102,
+            "RedBull",
+            DateTime::parse_from_rfc3339("2019-02-13T11:01:00.000Z")
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(
+            HighFrequencySmallInterval::new(2, 60).validate(&account, &tx),
+            None
+        );
+        assert_eq!(
+            HighFrequencySmallInterval::new(1, 60).validate(&account, &tx),
+            Some(OperationError::HighFrequencySmallInterval.into())
+        );
+    }
+
+    #[test]
+    fn blocked_merchant_category_blocked_mcc() {
+        let account = Account::new(100, true, vec![])
+            .with_blocked_categories(vec![MerchantCategory::Gambling]);
+        let tx = TX::new(10, "Casino", DateTime::default()).with_mcc(Some(7995));
+
+        assert_eq!(
+            BlockedMerchantCategory {}.validate(&account, &tx),
+            Some(OperationError::BlockedMerchantCategory.into())
+        );
+    }
+
+    #[test]
+    fn blocked_merchant_category_allowed_mcc() {
+        let account = Account::new(100, true, vec![])
+            .with_blocked_categories(vec![MerchantCategory::Gambling]);
+        let tx = TX::new(10, "Exchange", DateTime::default()).with_mcc(Some(6051));
+
+        assert_eq!(BlockedMerchantCategory {}.validate(&account, &tx), None);
+    }
+
+    #[test]
+    fn blocked_merchant_category_without_mcc() {
+        let account = Account::new(100, true, vec![])
+            .with_blocked_categories(vec![MerchantCategory::Gambling]);
+        let tx = TX::new(10, "Casino", DateTime::default());
+
+        assert_eq!(BlockedMerchantCategory {}.validate(&account, &tx), None);
+    }
+
+    fn spending_caps_account() -> Account {
+        Account::new(
+            1000,
+            true,
+            vec![
+                TX::new(
+                    30,
+                    "Nike",
+                    DateTime::parse_from_rfc3339("2019-02-13T11:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+                TX::new(
+                    40,
+                    "Coke",
+                    DateTime::parse_from_rfc3339("2019-02-14T01:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+            ],
+        )
+        .with_spending_caps(Some(Money::from(100)), Some(Money::from(150)))
+    }
+
+    #[test]
+    fn daily_limit_within_day() {
+        let account = spending_caps_account();
+        let tx = TX::new(
+            70,
+            "Pepsi",
+            DateTime::parse_from_rfc3339("2019-02-13T23:00:00.000Z")
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(DailyLimit::new(Tz::UTC).validate(&account, &tx), None);
+    }
+
+    #[test]
+    fn daily_limit_exceeded() {
+        let account = spending_caps_account();
+        let tx = TX::new(
+            71,
+            "Pepsi",
+            DateTime::parse_from_rfc3339("2019-02-13T23:00:00.000Z")
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(
+            DailyLimit::new(Tz::UTC).validate(&account, &tx),
+            Some(OperationError::DailyLimitExceeded.into())
+        );
+    }
+
+    #[test]
+    fn daily_limit_in_timezone() {
+        let account = spending_caps_account();
+        let tx = TX::new(
+            40,
+            "Pepsi",
+            DateTime::parse_from_rfc3339("2019-02-13T23:00:00.000Z")
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(DailyLimit::new(Tz::UTC).validate(&account, &tx), None);
+        assert_eq!(
+            DailyLimit::new(Tz::America__Sao_Paulo).validate(&account, &tx),
+            Some(OperationError::DailyLimitExceeded.into())
+        );
+    }
+
+    #[test]
+    fn daily_limit_with_active_holds() {
+        let time = DateTime::parse_from_rfc3339("2019-02-13T23:00:00.000Z")
+            .unwrap()
+            .into();
+        let mut account = spending_caps_account();
+        account
+            .place_hold(Hold::new("h-1", 50, "Hotel", time))
+            .unwrap();
+        account
+            .place_hold(Hold::new("h-2", 10, "Cab", time))
+            .unwrap();
+        account.release_hold("h-2").unwrap();
+        let tx = TX::new(21, "Pepsi", time);
+
+        assert_eq!(
+            DailyLimit::new(Tz::UTC).validate(&account, &tx),
+            Some(OperationError::DailyLimitExceeded.into())
+        );
+        assert_eq!(
+            MonthlyLimit::new(Tz::UTC).validate(&account, &TX::new(31, "Pepsi", time)),
+            Some(OperationError::MonthlyLimitExceeded.into())
+        );
+    }
+
+    #[test]
+    fn daily_limit_without_cap() {
+        let account = Account::new(1000, true, vec![]);
+        let tx = TX::new(500, "Pepsi", DateTime::default());
+
+        assert_eq!(DailyLimit::new(Tz::UTC).validate(&account, &tx), None);
+    }
+
+    #[test]
+    fn monthly_limit_exceeded() {
+        let account = spending_caps_account();
+        let tx = TX::new(
+            81,
+            "Pepsi",
+            DateTime::parse_from_rfc3339("2019-02-28T12:00:00.000Z")
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(
+            MonthlyLimit::new(Tz::UTC).validate(&account, &tx),
+            Some(OperationError::MonthlyLimitExceeded.into())
+        );
+    }
+
+    #[test]
+    fn monthly_limit_next_month() {
+        let account = spending_caps_account();
+        let tx = TX::new(
+            100,
+            "Pepsi",
+            DateTime::parse_from_rfc3339("2019-03-01T12:00:00.000Z")
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(MonthlyLimit::new(Tz::UTC).validate(&account, &tx), None);
+    }
+
+    #[test]
+    fn merchant_limit_max_amount() {
+        let account = Account::new(
+            1000,
+            true,
+            vec![
+                TX::new(
+                    30,
+                    "Netflix",
+                    DateTime::parse_from_rfc3339("2019-02-13T11:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+                TX::new(
+                    40,
+                    "Spotify",
+                    DateTime::parse_from_rfc3339("2019-02-13T12:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+                TX::new(
+                    30,
+                    "Netflix",
+                    DateTime::parse_from_rfc3339("2019-02-13T13:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+            ],
+        );
+        let tx = TX::new(
+            40,
+            "Netflix",
+            DateTime::parse_from_rfc3339("2019-02-13T14:00:00.000Z")
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(
+            MerchantLimit::new(86400, Some(Money::from(100)), None).validate(&account, &tx),
+            None
+        );
+        assert_eq!(
+            MerchantLimit::new(86400, Some("99.99".parse().unwrap()), None).validate(&account, &tx),
+            Some(OperationError::MerchantLimitExceeded.into())
+        );
+    }
+
+    #[test]
+    fn merchant_limit_max_amount_in_other_currency() {
+        let eur = Currency::from_code("EUR").unwrap();
+        let time = DateTime::parse_from_rfc3339("2019-02-13T14:00:00.000Z")
+            .unwrap()
+            .into();
+        let account = Account::new(
+            Money::new(100000, eur),
+            true,
+            vec![TX::new(Money::new(9000, eur), "Netflix", time)],
+        );
+        let tx = TX::new(Money::new(2000, eur), "Netflix", time);
+
+        assert_eq!(
+            MerchantLimit::new(86400, Some(Money::from(100)), None).validate(&account, &tx),
+            None
+        );
+        assert_eq!(
+            MerchantLimit::new(86400, Some("100 EUR".parse().unwrap()), None)
+                .validate(&account, &tx),
+            Some(OperationError::MerchantLimitExceeded.into())
+        );
+    }
+
+    #[test]
+    fn merchant_limit_max_txs() {
+        let account = Account::new(
+            1000,
+            true,
+            vec![
+                TX::new(
+                    30,
+                    "Netflix",
+                    DateTime::parse_from_rfc3339("2019-02-13T11:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+                TX::new(
+                    40,
+                    "Spotify",
+                    DateTime::parse_from_rfc3339("2019-02-13T12:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+                TX::new(
+                    30,
+                    "Netflix",
+                    DateTime::parse_from_rfc3339("2019-02-13T13:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+            ],
+        );
+        let tx = TX::new(
+            10,
+            "Netflix",
+            DateTime::parse_from_rfc3339("2019-02-13T14:00:00.000Z")
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(
+            MerchantLimit::new(86400, None, Some(3)).validate(&account, &tx),
+            None
+        );
+        assert_eq!(
+            MerchantLimit::new(86400, None, Some(2)).validate(&account, &tx),
+            Some(OperationError::MerchantLimitExceeded.into())
+        );
+    }
+
+    #[test]
+    fn merchant_limit_with_active_holds() {
+        let time = DateTime::parse_from_rfc3339("2019-02-13T14:00:00.000Z")
+            .unwrap()
+            .into();
+        let mut account = Account::new(
+            1000,
+            true,
+            vec![
+                TX::new(
+                    30,
+                    "Netflix",
+                    DateTime::parse_from_rfc3339("2019-02-13T11:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+                TX::new(
+                    40,
+                    "Spotify",
+                    DateTime::parse_from_rfc3339("2019-02-13T12:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+                TX::new(
+                    30,
+                    "Netflix",
+                    DateTime::parse_from_rfc3339("2019-02-13T13:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+            ],
+        );
+        account
+            .place_hold(Hold::new("h-1", 35, "Netflix", time))
+            .unwrap();
+        let tx = TX::new(5, "Netflix", time);
+
+        assert_eq!(
+            MerchantLimit::new(86400, Some(Money::from(100)), None).validate(&account, &tx),
+            None
+        );
+        assert_eq!(
+            MerchantLimit::new(86400, Some(Money::from(99)), None).validate(&account, &tx),
+            Some(OperationError::MerchantLimitExceeded.into())
+        );
+        assert_eq!(
+            MerchantLimit::new(86400, None, Some(3)).validate(&account, &tx),
+            Some(OperationError::MerchantLimitExceeded.into())
+        );
+    }
+
+    #[test]
+    fn merchant_limit_outside_window() {
+        let account = Account::new(
+            1000,
+            true,
+            vec![
+                TX::new(
+                    30,
+                    "Netflix",
+                    DateTime::parse_from_rfc3339("2019-02-13T11:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+                TX::new(
+                    40,
+                    "Spotify",
+                    DateTime::parse_from_rfc3339("2019-02-13T12:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+                TX::new(
+                    30,
+                    "Netflix",
+                    DateTime::parse_from_rfc3339("2019-02-13T13:00:00.000Z")
+                        .unwrap()
+                        .into(),
+                ),
+            ],
+        );
+        let tx = TX::new(
+            10,
+            "Netflix",
+            DateTime::parse_from_rfc3339("2019-02-13T14:00:00.000Z")
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(
+            MerchantLimit::new(3600, None, Some(1)).validate(&account, &tx),
+            Some(OperationError::MerchantLimitExceeded.into())
+        );
+        assert_eq!(
+            MerchantLimit::new(3599, None, Some(1)).validate(&account, &tx),
+            None
+        );
+    }
+
+    #[test]
+    fn explain_duplicated_tx() {
+        let time = DateTime::parse_from_rfc3339("2019-02-13T11:00:00.000Z")
+            .unwrap()
+            .into();
+        let account = Account::new(100, true, vec![TX::new(10, "Nike", time)]);
+        let tx = TX::new(10, "Nike", time);
+
+        let trace = DuplicatedTx::new(120).explain(&account, &tx);
+
+        assert_eq!(
+            trace,
+            RuleTrace::new("duplicated-tx", Some(OperationError::DuplicatedTx.into()))
+                .with_evidence(json!({
+                    "window-seconds": 120,
+                    "duplicate": {"merchant": "Nike", "amount": "10", "time": "2019-02-13T11:00:00Z"},
+                }))
+        );
+    }
+
+    #[test]
+    fn explain_high_frequency_small_interval() {
+        let tx = |time: &str| {
+            TX::new(
+                10,
+                "Nike",
+                DateTime::parse_from_rfc3339(time).unwrap().into(),
+            )
+        };
+        let account = Account::new(
+            100,
+            true,
+            vec![
+                tx("2019-02-13T11:00:00.000Z"),
+                tx("2019-02-13T11:00:30.000Z"),
+                tx("2019-02-13T11:01:00.000Z"),
+            ],
+        );
+
+        let trace = HighFrequencySmallInterval::new(2, 60)
+            .explain(&account, &tx("2019-02-13T11:01:30.000Z"));
+
+        assert_eq!(
+            trace,
+            RuleTrace::new(
+                "high-frequency-small-interval",
+                Some(OperationError::HighFrequencySmallInterval.into())
+            )
+            .with_evidence(json!({
+                "max-txs": 2,
+                "window-seconds": 60,
+                "times": ["2019-02-13T11:00:30Z", "2019-02-13T11:01:00Z", "2019-02-13T11:01:30Z"],
+            }))
+        );
+    }
+
+    #[test]
+    fn explain_merchant_limit() {
+        let account = Account::new(100, true, vec![TX::new(30, "Nike", DateTime::default())]);
+        let max_amount = Money::new(4990, Currency::USD);
+
+        let trace = MerchantLimit::new(60, Some(max_amount), None)
+            .explain(&account, &TX::new(20, "Nike", DateTime::default()));
+
+        assert_eq!(
+            trace,
+            RuleTrace::new(
+                "merchant-limit",
+                Some(OperationError::MerchantLimitExceeded.into())
+            )
+            .with_evidence(json!({
+                "window-seconds": 60,
+                "max-amount": "49.9",
+                "max-txs": null,
+                "spent": "50",
+                "txs": 2,
+            }))
+        );
+    }
+
+    #[test]
+    fn explain_rule_without_evidence() {
+        #[derive(Debug)]
+        struct AlwaysDecline;
+
+        impl AccountRule for AlwaysDecline {
+            fn validate(&self, _account: &Account, _tx: &TX) -> Option<Violation> {
+                Some(Violation::new("always-decline"))
+            }
+        }
+
+        let trace = AlwaysDecline.explain(
+            &Account::new(100, true, vec![]),
+            &TX::new(10, "Nike", DateTime::default()),
+        );
+
+        assert_eq!(
+            trace,
+            RuleTrace::new("AlwaysDecline", Some(Violation::new("always-decline")))
+        );
+    }
+
+    #[test]
+    fn name_generic_rule() {
+        #[derive(Debug)]
+        struct Wrapper<R>(R);
+
+        impl<R: AccountRule> AccountRule for Wrapper<R> {
+            fn validate(&self, account: &Account, tx: &TX) -> Option<Violation> {
+                self.0.validate(account, tx)
+            }
+        }
+
+        assert_eq!(Wrapper(InsufficientLimit {}).name(), "Wrapper");
+    }
 }
@@ -1,5 +1,19 @@
+mod config;
+mod executor;
 mod file;
+mod http;
+mod journal;
 mod models;
+mod output;
+mod snapshot;
+mod tcp;
 
-pub use file::parse_file;
-pub use models::FileOperation;
+pub use config::{config_warnings, load_config, load_fx_rates, load_rules};
+pub use executor::{lock_executor, Execute, OperationExecutor, SharedExecutor};
+pub use file::{parse_operation, parse_operations, process_operation};
+pub use http::serve_http;
+pub use journal::Journal;
+pub use models::{AccountData, FileOperation, InvalidOperation, OperationResult, TxData};
+pub use output::{format_result, format_shadow_summary, format_state, OutputFormat};
+pub use snapshot::{export_snapshot, import_snapshot};
+pub use tcp::serve;
@@ -0,0 +1,238 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+
+use crate::account::{parse_rules, Config, Currency, DslRule, FxRates};
+
+pub fn load_config(path: &Path) -> Result<Config> {
+    load_file(path, "config")
+}
+
+/// Settings in `config` that are likely not doing what was meant, to be reported at startup.
+pub fn config_warnings(config: &Config) -> Vec<String> {
+    let mut warnings = vec![];
+
+    if let Some(max_amount) = config.rules.merchant_limit.max_amount {
+        if max_amount.currency() != Currency::default() {
+            warnings.push(format!(
+                "merchant-limit max-amount is in {}, so it doesn't cap the spend of accounts in any \
+                other currency, {} ones included",
+                max_amount.currency(),
+                Currency::default()
+            ));
+        }
+    }
+
+    warnings
+}
+
+pub fn load_fx_rates(path: &Path) -> Result<FxRates> {
+    load_file(path, "FX rates")
+}
+
+pub fn load_rules(path: &Path) -> Result<Vec<DslRule>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Could not read rules file '{}'", path.display()))?;
+
+    parse_rules(&content).with_context(|| format!("Invalid rules file '{}'", path.display()))
+}
+
+fn load_file<T: DeserializeOwned>(path: &Path, kind: &str) -> Result<T> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Could not read {} file '{}'", kind, path.display()))?;
+
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => from_toml(&content),
+        Some("json") => from_json(&content),
+        _ => bail!(
+            "Unsupported {} file '{}', expected a .toml or .json file",
+            kind,
+            path.display()
+        ),
+    }
+    .with_context(|| format!("Invalid {} file '{}'", kind, path.display()))
+}
+
+fn from_toml<T: DeserializeOwned>(content: &str) -> Result<T> {
+    Ok(toml::from_str(content)?)
+}
+
+fn from_json<T: DeserializeOwned>(content: &str) -> Result<T> {
+    Ok(serde_json::from_str(content)?)
+}
+
+#[cfg(test)]
+mod config_loader {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_toml() {
+        let content = "
+            [rules.insufficient-limit]
+            enabled = false
+
+            [rules.high-frequency-small-interval]
+            max-txs = 5
+            window-seconds = 60
+        ";
+
+        let config: Config = from_toml(content).unwrap();
+
+        let mut expected_config = Config::default();
+        expected_config.rules.insufficient_limit.enabled = false;
+        expected_config.rules.high_frequency_small_interval.max_txs = 5;
+        expected_config
+            .rules
+            .high_frequency_small_interval
+            .window_seconds = 60;
+
+        assert_eq!(config, expected_config);
+    }
+
+    #[test]
+    fn parse_json() {
+        let content = "{\"rules\": {\"duplicated-tx\": {\"window-seconds\": 300}}}";
+
+        let config: Config = from_json(content).unwrap();
+
+        let mut expected_config = Config::default();
+        expected_config.rules.duplicated_tx.window_seconds = 300;
+
+        assert_eq!(config, expected_config);
+    }
+
+    #[test]
+    fn parse_holds() {
+        let content = "
+            [holds]
+            expiry-seconds = 3600
+        ";
+
+        let config: Config = from_toml(content).unwrap();
+
+        assert_eq!(config.holds.expiry_seconds, 3600);
+    }
+
+    #[test]
+    fn parse_spending_cap_timezone() {
+        let content = "
+            [rules.daily-limit]
+            timezone = \"America/Sao_Paulo\"
+        ";
+
+        let config: Config = from_toml(content).unwrap();
+
+        assert_eq!(
+            config.rules.daily_limit.timezone,
+            chrono_tz::Tz::America__Sao_Paulo
+        );
+        assert!(from_toml::<Config>("[rules.daily-limit]\ntimezone = \"Mars/Base\"").is_err());
+    }
+
+    #[test]
+    fn parse_merchant_limit() {
+        let content = "
+            [rules.merchant-limit]
+            window-seconds = 2592000
+            max-amount = 49.9
+            max-txs = 2
+        ";
+
+        let config: Config = from_toml(content).unwrap();
+
+        assert_eq!(config.rules.merchant_limit.window_seconds, 2592000);
+        assert_eq!(
+            config.rules.merchant_limit.max_amount,
+            Some("49.90".parse().unwrap())
+        );
+        assert_eq!(config.rules.merchant_limit.max_txs, Some(2));
+
+        let config: Config = from_toml("[rules.merchant-limit]\nmax-amount = \"90 EUR\"").unwrap();
+
+        assert_eq!(
+            config.rules.merchant_limit.max_amount,
+            Some("90.00 EUR".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn warn_about_merchant_limit_in_other_currency() {
+        let config: Config = from_toml("[rules.merchant-limit]\nmax-amount = \"90 EUR\"").unwrap();
+
+        assert_eq!(
+            config_warnings(&config),
+            vec![String::from(
+                "merchant-limit max-amount is in EUR, so it doesn't cap the spend of accounts in any \
+                other currency, USD ones included"
+            )]
+        );
+
+        let config: Config = from_toml("[rules.merchant-limit]\nmax-amount = 90").unwrap();
+
+        assert_eq!(config_warnings(&config), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reject_negative_windows() {
+        assert!(
+            from_toml::<Config>("[rules.high-frequency-small-interval]\nwindow-seconds = -1")
+                .is_err()
+        );
+        assert!(from_toml::<Config>("[rules.duplicated-tx]\nwindow-seconds = -120").is_err());
+        assert!(from_toml::<Config>("[rules.merchant-limit]\nwindow-seconds = -60").is_err());
+    }
+
+    #[test]
+    fn reject_unknown_fields() {
+        let content = "{\"rules\": {\"duplicated-tx\": {\"window\": 300}}}";
+
+        assert!(from_json::<Config>(content).is_err());
+    }
+
+    #[test]
+    fn parse_fx_rates() {
+        let content = "
+            markup-percent = 2.5
+
+            [rates]
+            \"EUR/USD\" = 1.0841
+        ";
+
+        let fx_rates: FxRates = from_toml(content).unwrap();
+
+        let expected_fx_rates: FxRates =
+            from_json("{\"markup-percent\": \"2.5\", \"rates\": {\"EUR/USD\": \"1.0841\"}}")
+                .unwrap();
+
+        assert_eq!(fx_rates, expected_fx_rates);
+    }
+
+    #[test]
+    fn load_rules_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rules.txt");
+        fs::write(
+            &path,
+            "# velocity\ncount(tx, within 1m) > 5 => \"velocity\"\ntx.amount > => \"x\"\n",
+        )
+        .unwrap();
+
+        let error = load_rules(&path).unwrap_err();
+
+        assert_eq!(
+            format!("{:#}", error),
+            format!(
+                "Invalid rules file '{}': line 3, column 13: expected a value, found '=>'",
+                path.display()
+            )
+        );
+
+        fs::write(&path, "count(tx, within 1m) > 5 => \"velocity\"\n").unwrap();
+
+        assert_eq!(load_rules(&path).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,373 @@
+use std::io::BufRead;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::account::{AccountState, Authorizer};
+use crate::io::{parse_operation, FileOperation, Journal};
+
+/// Executes parsed operations, either owning the authorizer or sharing it between connections.
+pub trait Execute {
+    fn execute(&mut self, operation: FileOperation) -> Result<AccountState>;
+}
+
+pub type SharedExecutor = Arc<Mutex<OperationExecutor>>;
+
+#[derive(Debug)]
+pub struct OperationExecutor {
+    authorizer: Authorizer,
+    journal: Option<Journal>,
+}
+
+impl OperationExecutor {
+    pub fn new(authorizer: Authorizer) -> Self {
+        Self {
+            authorizer,
+            journal: None,
+        }
+    }
+
+    pub fn authorizer(&self) -> &Authorizer {
+        &self.authorizer
+    }
+
+    /// Replays every operation recorded in the journal at `path` and appends the operations
+    /// accepted from now on to it. Returns the number of replayed operations, failing on the
+    /// first one that can't be applied as it was originally.
+    pub fn open_journal(&mut self, path: &Path) -> Result<usize> {
+        let mut replayed = 0;
+
+        if let Some(reader) = Journal::reader(path)? {
+            for (index, line) in reader.lines().enumerate() {
+                let line = line.context("Could not read journal entry")?;
+
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let operation = parse_operation(&line).with_context(|| {
+                    format!(
+                        "Invalid journal entry '{}' at line {} of '{}'",
+                        line,
+                        index + 1,
+                        path.display()
+                    )
+                })?;
+
+                let state = operation.replay(&mut self.authorizer);
+
+                if !state.violations().is_empty() {
+                    let violations: Vec<String> =
+                        state.violations().iter().map(|v| v.to_string()).collect();
+
+                    bail!(
+                        "Could not replay journal entry at line {} of '{}': {}",
+                        index + 1,
+                        path.display(),
+                        violations.join(", ")
+                    );
+                }
+
+                replayed += 1;
+            }
+        }
+
+        self.journal = Some(Journal::open(path)?);
+
+        Ok(replayed)
+    }
+
+    pub fn execute(&mut self, operation: FileOperation) -> Result<AccountState> {
+        let entry = self
+            .journal
+            .as_ref()
+            .filter(|_| !operation.is_processed(&self.authorizer))
+            .map(|_| operation.clone());
+
+        let state = operation.execute(&mut self.authorizer);
+
+        if let (Some(journal), Some(entry)) = (self.journal.as_mut(), entry) {
+            if let Some(entry) = entry.journal_entry(&state, &self.authorizer) {
+                journal.record(&entry)?;
+            }
+        }
+
+        Ok(state)
+    }
+}
+
+impl Execute for OperationExecutor {
+    fn execute(&mut self, operation: FileOperation) -> Result<AccountState> {
+        OperationExecutor::execute(self, operation)
+    }
+}
+
+impl Execute for SharedExecutor {
+    fn execute(&mut self, operation: FileOperation) -> Result<AccountState> {
+        lock_executor(self)?.execute(operation)
+    }
+}
+
+/// Locks the executor shared between connections, failing if a thread panicked holding it.
+pub fn lock_executor(executor: &SharedExecutor) -> Result<MutexGuard<'_, OperationExecutor>> {
+    executor
+        .lock()
+        .map_err(|_| anyhow!("Operation executor lock was poisoned"))
+}
+
+#[cfg(test)]
+mod operation_executor {
+    use super::*;
+
+    use std::fs;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::account::{Config, InsufficientLimit, OperationError};
+
+    fn operation(line: &str) -> FileOperation {
+        parse_operation(line).unwrap()
+    }
+
+    #[test]
+    fn journal_accepted_operations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+
+        let mut executor = OperationExecutor::new(Authorizer::new());
+        executor.open_journal(&path).unwrap();
+
+        executor
+            .execute(operation(
+                "{\"account\": {\"active-card\": true, \"available-limit\": 100}}",
+            ))
+            .unwrap();
+        executor
+            .execute(operation("{\"transaction\": {\"merchant\": \"Burger King\", \"amount\": 20, \"time\": \"2019-02-13T10:00:00.000Z\"}}"))
+            .unwrap();
+        executor
+            .execute(operation("{\"transaction\": {\"merchant\": \"Habbib's\", \"amount\": 90, \"time\": \"2019-02-13T11:00:00.000Z\"}}"))
+            .unwrap();
+
+        let journal = fs::read_to_string(&path).unwrap();
+
+        assert_eq!(
+            journal,
+            "{\"account\":{\"available-limit\":\"100\",\"active-card\":true}}\n\
+            {\"transaction\":{\"merchant\":\"Burger King\",\"amount\":\"20\",\"time\":\"2019-02-13T10:00:00Z\"}}\n"
+        );
+    }
+
+    #[test]
+    fn replay_journal_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+
+        let mut executor = OperationExecutor::new(Authorizer::new());
+        executor.open_journal(&path).unwrap();
+        executor
+            .execute(operation(
+                "{\"account\": {\"active-card\": true, \"available-limit\": 100}}",
+            ))
+            .unwrap();
+        executor
+            .execute(operation("{\"transaction\": {\"merchant\": \"Burger King\", \"amount\": 20, \"time\": \"2019-02-13T10:00:00.000Z\"}}"))
+            .unwrap();
+
+        let mut restarted_executor = OperationExecutor::new(Authorizer::new());
+        let replayed = restarted_executor.open_journal(&path).unwrap();
+
+        let state = restarted_executor
+            .execute(operation("{\"transaction\": {\"merchant\": \"Burger King\", \"amount\": 20, \"time\": \"2019-02-13T10:01:00.000Z\"}}"))
+            .unwrap();
+
+        assert_eq!(replayed, 2);
+        assert_eq!(
+            state,
+            AccountState::new(true, 80, vec![OperationError::DuplicatedTx])
+        );
+    }
+
+    #[test]
+    fn replay_journal_without_checking_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+
+        let mut executor = OperationExecutor::new(Authorizer::new());
+        executor.open_journal(&path).unwrap();
+        executor
+            .execute(operation(
+                "{\"account\": {\"active-card\": true, \"available-limit\": 100}}",
+            ))
+            .unwrap();
+        executor
+            .execute(operation("{\"transaction\": {\"merchant\": \"Burger King\", \"amount\": 20, \"time\": \"2019-02-13T10:00:00.000Z\"}}"))
+            .unwrap();
+
+        let mut config = Config::default();
+        config.rules.high_frequency_small_interval.max_txs = 0;
+
+        let mut restarted_executor =
+            OperationExecutor::new(Authorizer::builder().config(config).build());
+        restarted_executor.open_journal(&path).unwrap();
+
+        assert_eq!(
+            restarted_executor.authorizer().account_state(""),
+            Some(AccountState::new(true, 80, vec![]))
+        );
+    }
+
+    #[test]
+    fn replay_converted_tx_without_rates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        let fx_rates =
+            serde_json::from_str("{\"markup-percent\": 2.5, \"rates\": {\"EUR/USD\": 1.0841}}")
+                .unwrap();
+
+        let mut executor = OperationExecutor::new(Authorizer::builder().fx_rates(fx_rates).build());
+        executor.open_journal(&path).unwrap();
+        executor
+            .execute(operation(
+                "{\"account\": {\"active-card\": true, \"available-limit\": 100}}",
+            ))
+            .unwrap();
+        executor
+            .execute(operation("{\"transaction\": {\"merchant\": \"Burger King\", \"amount\": 20, \"currency\": \"EUR\", \"time\": \"2019-02-13T10:00:00.000Z\"}}"))
+            .unwrap();
+
+        let journal = fs::read_to_string(&path).unwrap();
+
+        assert_eq!(
+            journal.lines().nth(1),
+            Some("{\"transaction\":{\"merchant\":\"Burger King\",\"amount\":\"20\",\"currency\":\"EUR\",\"time\":\"2019-02-13T10:00:00Z\",\"converted\":{\"amount\":\"22.22 USD\",\"rate\":\"1.0841\",\"markup-percent\":\"2.5\"}}}")
+        );
+
+        let mut restarted_executor = OperationExecutor::new(Authorizer::new());
+        restarted_executor.open_journal(&path).unwrap();
+
+        assert_eq!(
+            restarted_executor.authorizer().account_state(""),
+            executor.authorizer().account_state("")
+        );
+    }
+
+    #[test]
+    fn keep_rejected_tx_ids_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+
+        let mut executor = OperationExecutor::new(Authorizer::new());
+        executor.open_journal(&path).unwrap();
+        executor
+            .execute(operation(
+                "{\"account\": {\"active-card\": true, \"available-limit\": 100}}",
+            ))
+            .unwrap();
+        executor
+            .execute(operation("{\"transaction\": {\"id\": \"tx-1\", \"merchant\": \"Burger King\", \"amount\": 120, \"time\": \"2019-02-13T10:00:00.000Z\"}}"))
+            .unwrap();
+        executor
+            .execute(operation("{\"transaction\": {\"id\": \"tx-1\", \"merchant\": \"Burger King\", \"amount\": 120, \"time\": \"2019-02-13T10:00:00.000Z\"}}"))
+            .unwrap();
+
+        let journal = fs::read_to_string(&path).unwrap();
+
+        assert_eq!(journal.lines().count(), 2);
+
+        let mut restarted_executor = OperationExecutor::new(Authorizer::new());
+        restarted_executor.open_journal(&path).unwrap();
+
+        let state = restarted_executor
+            .execute(operation("{\"transaction\": {\"id\": \"tx-1\", \"merchant\": \"Burger King\", \"amount\": 20, \"time\": \"2019-02-13T10:01:00.000Z\"}}"))
+            .unwrap();
+
+        assert_eq!(
+            state,
+            AccountState::new(true, 100, vec![OperationError::InsufficientLimit])
+        );
+        assert_eq!(
+            restarted_executor.authorizer().account_state(""),
+            Some(AccountState::new(true, 100, vec![]))
+        );
+    }
+
+    #[test]
+    fn reject_journal_entry_that_cant_be_replayed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        fs::write(
+            &path,
+            "{\"transaction\":{\"merchant\":\"Burger King\",\"amount\":\"20\",\"time\":\"2019-02-13T10:00:00Z\"}}\n",
+        )
+        .unwrap();
+
+        let mut executor = OperationExecutor::new(Authorizer::new());
+        let error = executor.open_journal(&path).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Could not replay journal entry at line 1 of '{}': account-not-initialized",
+                path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn leave_replayed_txs_out_of_shadow_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        let authorizer = || {
+            Authorizer::builder()
+                .shadow_rule(InsufficientLimit {})
+                .build()
+        };
+
+        let mut executor = OperationExecutor::new(authorizer());
+        executor.open_journal(&path).unwrap();
+        executor
+            .execute(operation(
+                "{\"account\": {\"active-card\": true, \"available-limit\": 100}}",
+            ))
+            .unwrap();
+        executor
+            .execute(operation("{\"transaction\": {\"merchant\": \"Burger King\", \"amount\": 20, \"time\": \"2019-02-13T10:00:00.000Z\"}}"))
+            .unwrap();
+
+        let mut restarted_executor = OperationExecutor::new(authorizer());
+        restarted_executor.open_journal(&path).unwrap();
+
+        assert_eq!(
+            restarted_executor
+                .authorizer()
+                .shadow_summary()
+                .checked_txs(),
+            0
+        );
+
+        restarted_executor
+            .execute(operation("{\"transaction\": {\"merchant\": \"Habbib's\", \"amount\": 20, \"time\": \"2019-02-13T11:00:00.000Z\"}}"))
+            .unwrap();
+
+        assert_eq!(
+            restarted_executor
+                .authorizer()
+                .shadow_summary()
+                .checked_txs(),
+            1
+        );
+    }
+
+    #[test]
+    fn reject_invalid_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        fs::write(&path, "{\"invalid_op\": {}}\n").unwrap();
+
+        let mut executor = OperationExecutor::new(Authorizer::new());
+
+        assert!(executor.open_journal(&path).is_err());
+    }
+}
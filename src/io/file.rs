@@ -1,25 +1,57 @@
+use std::io::BufRead;
+
 use anyhow::{Context, Result};
 
-use crate::account::{AccountState, Authorizer};
-use crate::io::FileOperation;
+use crate::io::{Execute, FileOperation, InvalidOperation, OperationResult};
 
-pub fn parse_file(file_content: String) -> Result<Vec<AccountState>> {
-    let mut op_executor = Authorizer::new();
-    let mut account_states = vec![];
+pub fn parse_operations<R, E, F>(
+    input: R,
+    executor: &mut E,
+    continue_on_error: bool,
+    mut on_result: F,
+) -> Result<()>
+where
+    R: BufRead,
+    E: Execute,
+    F: FnMut(OperationResult) -> Result<()>,
+{
+    for (index, line) in input.lines().enumerate() {
+        let line = line.context("Could not read operation")?;
 
-    for line in file_content.lines() {
-        let operation: FileOperation = serde_json::from_str(&line)
-            .with_context(|| format!("Invalid file operation '{}'", line))?;
+        if let Some(result) = process_operation(index + 1, &line, executor, continue_on_error)? {
+            on_result(result)?;
+        }
+    }
 
-        let account_state = match operation {
-            FileOperation::CreateAccount(acc) => op_executor.create_account(acc.to_account()),
-            FileOperation::ExecuteTX(tx) => op_executor.register_tx(tx.to_tx()),
-        };
+    Ok(())
+}
 
-        account_states.push(account_state);
+/// Parses and executes the operation at line `number` of the input, `None` for blank lines.
+pub fn process_operation<E: Execute>(
+    number: usize,
+    line: &str,
+    executor: &mut E,
+    continue_on_error: bool,
+) -> Result<Option<OperationResult>> {
+    if line.trim().is_empty() {
+        return Ok(None);
     }
 
-    return Ok(account_states);
+    let result = match parse_operation(line) {
+        Ok(operation) => OperationResult::Processed(executor.execute(operation)?),
+        Err(error) if continue_on_error => {
+            OperationResult::Invalid(InvalidOperation::new(number, line, &error))
+        }
+        Err(error) => {
+            return Err(error).with_context(|| format!("Invalid file operation '{}'", line))
+        }
+    };
+
+    Ok(Some(result))
+}
+
+pub fn parse_operation(line: &str) -> serde_json::Result<FileOperation> {
+    serde_json::from_str(line)
 }
 
 #[cfg(test)]
@@ -28,6 +60,23 @@ mod file_parser {
 
     use pretty_assertions::assert_eq;
 
+    use crate::account::{AccountState, Authorizer, Currency, Money, OperationError};
+    use crate::io::OperationExecutor;
+
+    fn parse(file_content: &str) -> Result<Vec<AccountState>> {
+        let mut executor = OperationExecutor::new(Authorizer::new());
+        let mut account_states = vec![];
+
+        parse_operations(file_content.as_bytes(), &mut executor, false, |result| {
+            if let OperationResult::Processed(state) = result {
+                account_states.push(state);
+            }
+            Ok(())
+        })?;
+
+        Ok(account_states)
+    }
+
     #[test]
     fn handle_successful_file_operation() {
         let file_content = String::from(
@@ -35,7 +84,7 @@ mod file_parser {
             {\"transaction\": {\"merchant\": \"Burger King\", \"amount\": 20, \"time\": \"2019-02-13T10:00:00.000Z\"}}"
         );
 
-        let account_states = parse_file(file_content).unwrap();
+        let account_states = parse(&file_content).unwrap();
         let expected_account_states = vec![
             AccountState::new(true, 100, vec![]),
             AccountState::new(true, 80, vec![]),
@@ -48,8 +97,217 @@ mod file_parser {
     fn handle_invalid_file_operations() {
         let file_content = String::from("{\"invalid_op\": {}}");
 
-        let result = parse_file(file_content);
+        let result = parse(&file_content);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn emit_states_before_reading_next_line() {
+        let file_content = "{\"account\": {\"active-card\": true, \"available-limit\": 100}}
+            {\"invalid_op\": {}}";
+        let mut executor = OperationExecutor::new(Authorizer::new());
+        let mut account_states = vec![];
+
+        let result = parse_operations(file_content.as_bytes(), &mut executor, false, |result| {
+            if let OperationResult::Processed(state) = result {
+                account_states.push(state);
+            }
+            Ok(())
+        });
 
         assert!(result.is_err());
+        assert_eq!(account_states, vec![AccountState::new(true, 100, vec![])]);
+    }
+
+    #[test]
+    fn handle_operations_for_many_accounts() {
+        let file_content = "{\"account\": {\"account-id\": \"a\", \"active-card\": true, \"available-limit\": 100}}
+            {\"account\": {\"account-id\": \"b\", \"active-card\": true, \"available-limit\": 50}}
+            {\"transaction\": {\"account-id\": \"b\", \"merchant\": \"Burger King\", \"amount\": 20, \"time\": \"2019-02-13T10:00:00.000Z\"}}
+            {\"transaction\": {\"account-id\": \"c\", \"merchant\": \"Burger King\", \"amount\": 20, \"time\": \"2019-02-13T10:00:00.000Z\"}}";
+
+        let account_states = parse(file_content).unwrap();
+        let expected_account_states = vec![
+            AccountState::new(true, 100, vec![]).with_account_id("a"),
+            AccountState::new(true, 50, vec![]).with_account_id("b"),
+            AccountState::new(true, 30, vec![]).with_account_id("b"),
+            AccountState::not_initialized().with_account_id("c"),
+        ];
+
+        assert_eq!(account_states, expected_account_states);
+    }
+
+    #[test]
+    fn handle_card_status_operations() {
+        let file_content = "{\"account\": {\"active-card\": true, \"available-limit\": 100}}
+            {\"card-status\": {\"active-card\": false}}
+            {\"transaction\": {\"merchant\": \"Burger King\", \"amount\": 20, \"time\": \"2019-02-13T10:00:00.000Z\"}}
+            {\"card-status\": {\"account-id\": \"b\", \"active-card\": true}}";
+
+        let account_states = parse(file_content).unwrap();
+        let expected_account_states = vec![
+            AccountState::new(true, 100, vec![]),
+            AccountState::new(false, 100, vec![]),
+            AccountState::inactive(100),
+            AccountState::not_initialized().with_account_id("b"),
+        ];
+
+        assert_eq!(account_states, expected_account_states);
+    }
+
+    #[test]
+    fn handle_credit_limit_operations() {
+        let file_content = "{\"account\": {\"active-card\": true, \"available-limit\": 100}}
+            {\"transaction\": {\"merchant\": \"Burger King\", \"amount\": 80, \"time\": \"2019-02-13T10:00:00.000Z\"}}
+            {\"credit-limit\": {\"limit\": 300}}
+            {\"credit-limit\": {\"limit\": 50}}";
+
+        let account_states = parse(file_content).unwrap();
+        let expected_account_states = vec![
+            AccountState::new(true, 100, vec![]),
+            AccountState::new(true, 20, vec![]),
+            AccountState::new(true, 220, vec![]),
+            AccountState::new(true, 220, vec![OperationError::LimitBelowConsumed]),
+        ];
+
+        assert_eq!(account_states, expected_account_states);
+    }
+
+    #[test]
+    fn handle_refund_operations() {
+        let file_content = "{\"account\": {\"active-card\": true, \"available-limit\": 100}}
+            {\"transaction\": {\"id\": \"tx-1\", \"merchant\": \"Burger King\", \"amount\": 80, \"time\": \"2019-02-13T10:00:00.000Z\"}}
+            {\"refund\": {\"transaction-id\": \"tx-1\"}}
+            {\"refund\": {\"transaction-id\": \"tx-1\"}}
+            {\"refund\": {\"transaction-id\": \"tx-2\"}}";
+
+        let account_states = parse(file_content).unwrap();
+        let expected_account_states = vec![
+            AccountState::new(true, 100, vec![]),
+            AccountState::new(true, 20, vec![]),
+            AccountState::new(true, 100, vec![]),
+            AccountState::new(true, 100, vec![OperationError::TransactionAlreadyReversed]),
+            AccountState::new(true, 100, vec![OperationError::TransactionNotFound]),
+        ];
+
+        assert_eq!(account_states, expected_account_states);
+    }
+
+    #[test]
+    fn handle_hold_operations() {
+        let file_content = "{\"account\": {\"active-card\": true, \"available-limit\": 100}}
+            {\"hold\": {\"id\": \"h-1\", \"merchant\": \"Hotel\", \"amount\": 60, \"time\": \"2019-02-13T10:00:00.000Z\"}}
+            {\"hold\": {\"id\": \"h-2\", \"merchant\": \"Fuel\", \"amount\": 30, \"time\": \"2019-02-13T10:10:00.000Z\"}}
+            {\"capture\": {\"hold-id\": \"h-1\", \"amount\": 50, \"time\": \"2019-02-14T10:00:00.000Z\"}}
+            {\"release\": {\"hold-id\": \"h-2\", \"time\": \"2019-02-14T10:00:00.000Z\"}}
+            {\"release\": {\"hold-id\": \"h-3\", \"time\": \"2019-02-14T10:00:00.000Z\"}}";
+
+        let account_states = parse(file_content).unwrap();
+        let expected_account_states = vec![
+            AccountState::new(true, 100, vec![]),
+            AccountState::new(true, 40, vec![]),
+            AccountState::new(true, 10, vec![]),
+            AccountState::new(true, 20, vec![]),
+            AccountState::new(true, 50, vec![]),
+            AccountState::new(true, 50, vec![OperationError::HoldNotFound]),
+        ];
+
+        assert_eq!(account_states, expected_account_states);
+    }
+
+    #[test]
+    fn handle_decimal_amounts_and_currencies() {
+        let file_content = "{\"account\": {\"active-card\": true, \"available-limit\": 100.5, \"currency\": \"EUR\"}}
+            {\"transaction\": {\"merchant\": \"Burger King\", \"amount\": 20.25, \"currency\": \"EUR\", \"time\": \"2019-02-13T10:00:00.000Z\"}}
+            {\"transaction\": {\"merchant\": \"Habbib's\", \"amount\": 10, \"time\": \"2019-02-13T10:05:00.000Z\"}}
+            {\"transaction\": {\"merchant\": \"Habbib's\", \"amount\": 10.001, \"currency\": \"EUR\", \"time\": \"2019-02-13T10:10:00.000Z\"}}";
+
+        let account_states = parse(file_content).unwrap();
+        let eur = Currency::from_code("EUR").unwrap();
+        let expected_account_states = vec![
+            AccountState::new(true, Money::new(10050, eur), vec![]),
+            AccountState::new(true, Money::new(8025, eur), vec![]),
+            AccountState::new(
+                true,
+                Money::new(8025, eur),
+                vec![OperationError::CurrencyMismatch],
+            ),
+            AccountState::new(
+                true,
+                Money::new(8025, eur),
+                vec![OperationError::InvalidAmount],
+            ),
+        ];
+
+        assert_eq!(account_states, expected_account_states);
+    }
+
+    #[test]
+    fn handle_blocked_merchant_categories() {
+        let file_content = "{\"account\": {\"active-card\": true, \"available-limit\": 100, \"blocked-categories\": [\"gambling\", \"crypto\"]}}
+            {\"transaction\": {\"merchant\": \"Casino\", \"amount\": 20, \"mcc\": 7995, \"time\": \"2019-02-13T10:00:00.000Z\"}}
+            {\"transaction\": {\"merchant\": \"Burger King\", \"amount\": 20, \"mcc\": 5814, \"time\": \"2019-02-13T10:05:00.000Z\"}}";
+
+        let account_states = parse(file_content).unwrap();
+        let expected_account_states = vec![
+            AccountState::new(true, 100, vec![]),
+            AccountState::new(true, 100, vec![OperationError::BlockedMerchantCategory]),
+            AccountState::new(true, 80, vec![]),
+        ];
+
+        assert_eq!(account_states, expected_account_states);
+    }
+
+    #[test]
+    fn handle_spending_caps() {
+        let file_content = "{\"account\": {\"active-card\": true, \"available-limit\": 1000, \"daily-limit\": 50, \"monthly-limit\": 80}}
+            {\"transaction\": {\"merchant\": \"Burger King\", \"amount\": 40, \"time\": \"2019-02-13T10:00:00.000Z\"}}
+            {\"transaction\": {\"merchant\": \"Habbib's\", \"amount\": 20, \"time\": \"2019-02-13T12:00:00.000Z\"}}
+            {\"transaction\": {\"merchant\": \"Habbib's\", \"amount\": 50, \"time\": \"2019-02-14T12:00:00.000Z\"}}";
+
+        let account_states = parse(file_content).unwrap();
+        let expected_account_states = vec![
+            AccountState::new(true, 1000, vec![]),
+            AccountState::new(true, 960, vec![]),
+            AccountState::new(true, 960, vec![OperationError::DailyLimitExceeded]),
+            AccountState::new(true, 960, vec![OperationError::MonthlyLimitExceeded]),
+        ];
+
+        assert_eq!(account_states, expected_account_states);
+    }
+
+    #[test]
+    fn skip_blank_lines() {
+        let file_content = "\n{\"account\": {\"active-card\": true, \"available-limit\": 100}}\n\n";
+
+        let account_states = parse(file_content).unwrap();
+
+        assert_eq!(account_states, vec![AccountState::new(true, 100, vec![])]);
+    }
+
+    #[test]
+    fn continue_on_invalid_file_operations() {
+        let file_content = "{\"account\": {\"active-card\": true, \"available-limit\": 100}}
+{\"invalid_op\": {}}
+
+{\"transaction\": {\"merchant\": \"Burger King\", \"amount\": 20, \"time\": \"2019-02-13T10:00:00.000Z\"}}";
+        let mut executor = OperationExecutor::new(Authorizer::new());
+        let mut results = vec![];
+
+        parse_operations(file_content.as_bytes(), &mut executor, true, |result| {
+            results.push(result);
+            Ok(())
+        })
+        .unwrap();
+
+        let error = serde_json::from_str::<FileOperation>("{\"invalid_op\": {}}").unwrap_err();
+        let expected_results = vec![
+            OperationResult::Processed(AccountState::new(true, 100, vec![])),
+            OperationResult::Invalid(InvalidOperation::new(2, "{\"invalid_op\": {}}", &error)),
+            OperationResult::Processed(AccountState::new(true, 80, vec![])),
+        ];
+
+        assert_eq!(results, expected_results);
     }
 }
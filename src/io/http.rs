@@ -0,0 +1,317 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::account::{AccountState, OperationError};
+use crate::io::{
+    format_shadow_summary, format_state, lock_executor, AccountData, FileOperation, OutputFormat,
+    SharedExecutor, TxData,
+};
+
+type HttpResponse = Response<std::io::Cursor<Vec<u8>>>;
+
+/// Serves `POST /accounts`, `POST /transactions` and `GET /accounts/{id}`, answering with the
+/// resulting account state, and `GET /shadow-summary`.
+pub fn serve_http(server: Server, executor: SharedExecutor, format: OutputFormat) -> Result<()> {
+    for mut request in server.incoming_requests() {
+        let response = match handle_request(&mut request, &executor, format) {
+            Ok(response) => response,
+            Err(error) => error_response(500, &format!("{:#}", error)),
+        };
+
+        if let Err(error) = request.respond(response) {
+            eprintln!("Could not send response: {}", error);
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_request(
+    request: &mut Request,
+    executor: &SharedExecutor,
+    format: OutputFormat,
+) -> Result<HttpResponse> {
+    let url = request.url().to_string();
+    let path = url.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match (request.method(), segments.as_slice()) {
+        (Method::Post, ["accounts"]) => {
+            let account = match parse_body::<AccountData>(request) {
+                Ok(account) => account,
+                Err(error) => return Ok(error_response(400, &error.to_string())),
+            };
+
+            let state = execute(executor, FileOperation::CreateAccount(account))?;
+
+            state_response(&state, 201, format)
+        }
+        (Method::Post, ["transactions"]) => {
+            let tx = match parse_body::<TxData>(request) {
+                Ok(tx) => tx,
+                Err(error) => return Ok(error_response(400, &error.to_string())),
+            };
+
+            let state = execute(executor, FileOperation::ExecuteTX(tx))?;
+
+            state_response(&state, 200, format)
+        }
+        (Method::Get, ["accounts", account_id]) => {
+            let state = lock_executor(executor)?
+                .authorizer()
+                .account_state(account_id)
+                .unwrap_or_else(|| AccountState::not_initialized().with_account_id(account_id));
+
+            state_response(&state, 200, format)
+        }
+        (Method::Get, ["shadow-summary"]) => {
+            let body = format_shadow_summary(
+                lock_executor(executor)?.authorizer().shadow_summary(),
+                format,
+            )?;
+
+            Ok(json_response(200, body))
+        }
+        (_, ["accounts"])
+        | (_, ["transactions"])
+        | (_, ["accounts", _])
+        | (_, ["shadow-summary"]) => Ok(error_response(405, "Method not allowed")),
+        _ => Ok(error_response(404, "Not found")),
+    }
+}
+
+fn parse_body<T: DeserializeOwned>(request: &mut Request) -> Result<T> {
+    let mut body = String::new();
+    request.as_reader().read_to_string(&mut body)?;
+
+    Ok(serde_json::from_str(&body)?)
+}
+
+fn execute(executor: &SharedExecutor, operation: FileOperation) -> Result<AccountState> {
+    lock_executor(executor)?.execute(operation)
+}
+
+/// Maps the violations of a processed operation to a status code, using `success_status` when
+/// the operation was accepted.
+fn status_code(state: &AccountState, success_status: u16) -> u16 {
+    let violations = state.violations();
+
+    if violations.is_empty() {
+        success_status
+    } else if violations.contains(&OperationError::AccountNotInitialized.into()) {
+        404
+    } else if violations.contains(&OperationError::AccountAlreadyInitialized.into()) {
+        409
+    } else {
+        422
+    }
+}
+
+fn state_response(
+    state: &AccountState,
+    success_status: u16,
+    format: OutputFormat,
+) -> Result<HttpResponse> {
+    let body = format_state(state, format)?;
+
+    Ok(json_response(status_code(state, success_status), body))
+}
+
+fn error_response(status: u16, message: &str) -> HttpResponse {
+    let body = serde_json::json!({ "error": { "message": message } }).to_string();
+
+    json_response(status, body)
+}
+
+fn json_response(status: u16, body: String) -> HttpResponse {
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+
+    Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type)
+}
+
+#[cfg(test)]
+mod http_server {
+    use super::*;
+
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::account::Authorizer;
+    use crate::io::OperationExecutor;
+
+    fn start_server() -> SocketAddr {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let address = server.server_addr().to_ip().unwrap();
+        let executor = Arc::new(Mutex::new(OperationExecutor::new(Authorizer::new())));
+
+        thread::spawn(move || serve_http(server, executor, OutputFormat::Envelope));
+
+        address
+    }
+
+    fn request(address: SocketAddr, method: &str, path: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        let status = response[9..12].parse().unwrap();
+        let body = response.split("\r\n\r\n").nth(1).unwrap().to_string();
+
+        (status, body)
+    }
+
+    #[test]
+    fn create_account() {
+        let address = start_server();
+
+        let response = request(
+            address,
+            "POST",
+            "/accounts",
+            "{\"account-id\": \"a\", \"active-card\": true, \"available-limit\": 100}",
+        );
+
+        assert_eq!(
+            response,
+            (
+                201,
+                String::from("{\"account\":{\"account-id\":\"a\",\"active-card\":true,\"available-limit\":100},\"violations\":[]}")
+            )
+        );
+    }
+
+    #[test]
+    fn create_duplicated_account() {
+        let address = start_server();
+        let body = "{\"account-id\": \"a\", \"active-card\": true, \"available-limit\": 100}";
+
+        request(address, "POST", "/accounts", body);
+        let (status, _) = request(address, "POST", "/accounts", body);
+
+        assert_eq!(status, 409);
+    }
+
+    #[test]
+    fn register_transactions() {
+        let address = start_server();
+
+        request(
+            address,
+            "POST",
+            "/accounts",
+            "{\"account-id\": \"a\", \"active-card\": true, \"available-limit\": 100}",
+        );
+
+        let authorized = request(
+            address,
+            "POST",
+            "/transactions",
+            "{\"account-id\": \"a\", \"merchant\": \"Burger King\", \"amount\": 20, \"time\": \"2019-02-13T10:00:00.000Z\"}",
+        );
+        let rejected = request(
+            address,
+            "POST",
+            "/transactions",
+            "{\"account-id\": \"a\", \"merchant\": \"Habbib's\", \"amount\": 90, \"time\": \"2019-02-13T11:00:00.000Z\"}",
+        );
+        let uninitialized = request(
+            address,
+            "POST",
+            "/transactions",
+            "{\"account-id\": \"b\", \"merchant\": \"Habbib's\", \"amount\": 90, \"time\": \"2019-02-13T11:00:00.000Z\"}",
+        );
+
+        assert_eq!(
+            authorized,
+            (
+                200,
+                String::from("{\"account\":{\"account-id\":\"a\",\"active-card\":true,\"available-limit\":80},\"violations\":[]}")
+            )
+        );
+        assert_eq!(
+            rejected,
+            (
+                422,
+                String::from("{\"account\":{\"account-id\":\"a\",\"active-card\":true,\"available-limit\":80},\"violations\":[\"insufficient-limit\"]}")
+            )
+        );
+        assert_eq!(uninitialized.0, 404);
+    }
+
+    #[test]
+    fn get_account() {
+        let address = start_server();
+
+        request(
+            address,
+            "POST",
+            "/accounts",
+            "{\"account-id\": \"a\", \"active-card\": true, \"available-limit\": 100}",
+        );
+
+        let found = request(address, "GET", "/accounts/a", "");
+        let not_found = request(address, "GET", "/accounts/b", "");
+
+        assert_eq!(
+            found,
+            (
+                200,
+                String::from("{\"account\":{\"account-id\":\"a\",\"active-card\":true,\"available-limit\":100},\"violations\":[]}")
+            )
+        );
+        assert_eq!(
+            not_found,
+            (
+                404,
+                String::from("{\"account\":{\"account-id\":\"b\",\"active-card\":false,\"available-limit\":0},\"violations\":[\"account-not-initialized\"]}")
+            )
+        );
+    }
+
+    #[test]
+    fn get_shadow_summary() {
+        let address = start_server();
+
+        let response = request(address, "GET", "/shadow-summary", "");
+        let (invalid_method, _) = request(address, "POST", "/shadow-summary", "");
+
+        assert_eq!(
+            response,
+            (
+                200,
+                String::from("{\"shadow-summary\":{\"checked-txs\":0,\"flagged-txs\":0,\"would-decline\":0,\"violations\":{}}}")
+            )
+        );
+        assert_eq!(invalid_method, 405);
+    }
+
+    #[test]
+    fn reject_invalid_requests() {
+        let address = start_server();
+
+        let (invalid_body, _) = request(address, "POST", "/accounts", "{\"nope\": 1}");
+        let (unknown_route, _) = request(address, "GET", "/nope", "");
+        let (invalid_method, _) = request(address, "DELETE", "/accounts/a", "");
+
+        assert_eq!(invalid_body, 400);
+        assert_eq!(unknown_route, 404);
+        assert_eq!(invalid_method, 405);
+    }
+}
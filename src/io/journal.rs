@@ -0,0 +1,46 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::io::FileOperation;
+
+/// Append-only log of the operations accepted by the authorizer, stored as json lines in the
+/// same format as the input operations.
+#[derive(Debug)]
+pub struct Journal {
+    file: File,
+}
+
+impl Journal {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Could not open journal '{}'", path.display()))?;
+
+        Ok(Self { file })
+    }
+
+    pub fn reader(path: &Path) -> Result<Option<BufReader<File>>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let file = File::open(path)
+            .with_context(|| format!("Could not read journal '{}'", path.display()))?;
+
+        Ok(Some(BufReader::new(file)))
+    }
+
+    pub fn record(&mut self, operation: &FileOperation) -> Result<()> {
+        let mut entry = serde_json::to_string(operation)?;
+        entry.push('\n');
+
+        self.file
+            .write_all(entry.as_bytes())
+            .context("Could not write to journal")
+    }
+}
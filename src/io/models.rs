@@ -1,48 +1,346 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::account::{Account, TX};
+use crate::account::{
+    Account, AccountState, Authorizer, Currency, Decimal, Hold, MerchantCategory, Money,
+    OperationError, TX,
+};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum FileOperation {
     #[serde(rename = "account")]
     CreateAccount(AccountData),
     #[serde(rename = "transaction")]
     ExecuteTX(TxData),
+    #[serde(rename = "card-status")]
+    UpdateCardStatus(CardStatusData),
+    #[serde(rename = "credit-limit")]
+    UpdateCreditLimit(CreditLimitData),
+    #[serde(rename = "refund")]
+    RefundTX(RefundData),
+    #[serde(rename = "hold")]
+    PlaceHold(HoldData),
+    #[serde(rename = "capture")]
+    CaptureHold(CaptureData),
+    #[serde(rename = "release")]
+    ReleaseHold(ReleaseData),
+    #[serde(rename = "blocked-categories")]
+    UpdateBlockedCategories(BlockedCategoriesData),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl FileOperation {
+    pub fn execute(self, authorizer: &mut Authorizer) -> AccountState {
+        self.run(authorizer, false)
+    }
+
+    /// Applies an operation accepted before a restart, taking transactions and holds as already
+    /// authorized instead of checking the rules again.
+    pub fn replay(self, authorizer: &mut Authorizer) -> AccountState {
+        self.run(authorizer, true)
+    }
+
+    /// Whether the operation is a transaction whose `id` the account already processed, so
+    /// executing it again only returns the earlier result.
+    pub fn is_processed(&self, authorizer: &Authorizer) -> bool {
+        match self {
+            FileOperation::ExecuteTX(tx) => tx
+                .id
+                .as_deref()
+                .is_some_and(|id| authorizer.processed_tx_state(&tx.account_id, id).is_some()),
+            _ => false,
+        }
+    }
+
+    /// Entry recorded in the journal for the operation once executed with `state`, if any.
+    /// Converted transactions carry the amount and rate they were authorized with, so replays
+    /// don't depend on the rates loaded then, and rejected transactions with an `id` carry the
+    /// result they got, so the `id` keeps returning it after a restart.
+    pub fn journal_entry(self, state: &AccountState, authorizer: &Authorizer) -> Option<Self> {
+        match self {
+            FileOperation::ExecuteTX(tx) if !state.violations().is_empty() => {
+                let rejected = tx
+                    .id
+                    .as_deref()
+                    .and_then(|id| authorizer.processed_tx_state(&tx.account_id, id))?
+                    .clone();
+
+                Some(FileOperation::ExecuteTX(TxData {
+                    rejected: Some(Box::new(rejected)),
+                    ..tx
+                }))
+            }
+            FileOperation::ExecuteTX(tx) => {
+                let fx_rates = authorizer.fx_rates();
+                let currency = tx.currency.unwrap_or_default();
+                let converted = state.converted_amount().and_then(|amount| {
+                    fx_rates
+                        .rate(currency, amount.currency())
+                        .map(|rate| ConversionData {
+                            amount,
+                            rate,
+                            markup_percent: fx_rates.markup_percent(),
+                        })
+                });
+
+                Some(FileOperation::ExecuteTX(TxData { converted, ..tx }))
+            }
+            _ if !state.violations().is_empty() => None,
+            operation => Some(operation),
+        }
+    }
+
+    fn run(self, authorizer: &mut Authorizer, replay: bool) -> AccountState {
+        match self {
+            FileOperation::CreateAccount(acc) => {
+                let account_id = acc.account_id.clone();
+
+                match acc.into_account() {
+                    Ok(account) => authorizer.create_account(account_id, account),
+                    Err(error) => authorizer.reject_account(&account_id, vec![error]),
+                }
+            }
+            FileOperation::ExecuteTX(TxData {
+                account_id,
+                id: Some(tx_id),
+                rejected: Some(state),
+                ..
+            }) if replay => authorizer.replay_rejected_tx(&account_id, tx_id, *state),
+            FileOperation::ExecuteTX(tx) => {
+                let account_id = tx.account_id.clone();
+                let converted_amount = tx.converted.as_ref().map(|c| c.amount);
+
+                match tx.into_tx() {
+                    Ok(tx) if replay => authorizer.replay_tx(&account_id, tx, converted_amount),
+                    Ok(tx) => authorizer.register_tx(&account_id, tx),
+                    Err(error) => authorizer.reject(&account_id, vec![error]),
+                }
+            }
+            FileOperation::UpdateCardStatus(status) => {
+                authorizer.update_card_status(&status.account_id, status.active_card)
+            }
+            FileOperation::UpdateCreditLimit(limit) => {
+                match to_money(limit.limit, limit.currency) {
+                    Ok(total_limit) => {
+                        authorizer.update_credit_limit(&limit.account_id, total_limit)
+                    }
+                    Err(error) => authorizer.reject(&limit.account_id, vec![error]),
+                }
+            }
+            FileOperation::RefundTX(refund) => {
+                authorizer.refund_tx(&refund.account_id, &refund.transaction_id)
+            }
+            FileOperation::PlaceHold(hold) => {
+                let account_id = hold.account_id.clone();
+
+                match hold.into_hold() {
+                    Ok(hold) if replay => authorizer.replay_hold(&account_id, hold),
+                    Ok(hold) => authorizer.place_hold(&account_id, hold),
+                    Err(error) => authorizer.reject(&account_id, vec![error]),
+                }
+            }
+            FileOperation::CaptureHold(capture) => match to_money(capture.amount, capture.currency)
+            {
+                Ok(amount) => authorizer.capture_hold(
+                    &capture.account_id,
+                    &capture.hold_id,
+                    amount,
+                    capture.time,
+                ),
+                Err(error) => authorizer.reject(&capture.account_id, vec![error]),
+            },
+            FileOperation::ReleaseHold(release) => {
+                authorizer.release_hold(&release.account_id, &release.hold_id, release.time)
+            }
+            FileOperation::UpdateBlockedCategories(categories) => authorizer
+                .update_blocked_categories(&categories.account_id, categories.blocked_categories),
+        }
+    }
+}
+
+/// Amounts given without a currency are in the default one.
+fn to_money(amount: Decimal, currency: Option<Currency>) -> Result<Money, OperationError> {
+    Money::from_decimal(amount, currency.unwrap_or_default())
+}
+
+#[derive(Debug, PartialEq)]
+pub enum OperationResult {
+    Processed(AccountState),
+    Invalid(InvalidOperation),
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct InvalidOperation {
+    line: usize,
+    input: String,
+    message: String,
+}
+
+impl InvalidOperation {
+    pub fn new(line: usize, input: &str, error: &serde_json::Error) -> Self {
+        Self {
+            line,
+            input: input.to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct AccountData {
-    available_limit: u32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    account_id: String,
+    available_limit: Decimal,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    currency: Option<Currency>,
     active_card: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    blocked_categories: Vec<MerchantCategory>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    daily_limit: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    monthly_limit: Option<Decimal>,
 }
 
 impl AccountData {
-    pub fn to_account(self) -> Account {
-        Account::new(self.available_limit, self.active_card, vec![])
+    pub fn into_account(self) -> Result<Account, OperationError> {
+        let available_limit = to_money(self.available_limit, self.currency)?;
+
+        let daily_limit = self
+            .daily_limit
+            .map(|limit| to_money(limit, self.currency))
+            .transpose()?;
+        let monthly_limit = self
+            .monthly_limit
+            .map(|limit| to_money(limit, self.currency))
+            .transpose()?;
+
+        Ok(Account::new(available_limit, self.active_card, vec![])
+            .with_blocked_categories(self.blocked_categories)
+            .with_spending_caps(daily_limit, monthly_limit))
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct TxData {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    account_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
     merchant: String,
-    amount: u32,
+    amount: Decimal,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    currency: Option<Currency>,
     time: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mcc: Option<u16>,
+    /// Conversion to the account currency the transaction was authorized with, only recorded in
+    /// the journal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    converted: Option<ConversionData>,
+    /// Result the transaction was rejected with, only recorded in the journal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rejected: Option<Box<AccountState>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ConversionData {
+    amount: Money,
+    rate: Decimal,
+    markup_percent: Decimal,
 }
 
 impl TxData {
-    pub fn to_tx(self) -> TX {
-        TX {
-            amount: self.amount,
-            merchant: self.merchant,
-            time: self.time,
-        }
+    pub fn into_tx(self) -> Result<TX, OperationError> {
+        let amount = to_money(self.amount, self.currency)?;
+
+        Ok(TX::new(amount, &self.merchant, self.time)
+            .with_id(self.id)
+            .with_mcc(self.mcc))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CardStatusData {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    account_id: String,
+    active_card: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CreditLimitData {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    account_id: String,
+    limit: Decimal,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    currency: Option<Currency>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RefundData {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    account_id: String,
+    transaction_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HoldData {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    account_id: String,
+    id: String,
+    merchant: String,
+    amount: Decimal,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    currency: Option<Currency>,
+    time: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mcc: Option<u16>,
+}
+
+impl HoldData {
+    pub fn into_hold(self) -> Result<Hold, OperationError> {
+        let amount = to_money(self.amount, self.currency)?;
+
+        Ok(Hold::new(&self.id, amount, &self.merchant, self.time).with_mcc(self.mcc))
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CaptureData {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    account_id: String,
+    hold_id: String,
+    amount: Decimal,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    currency: Option<Currency>,
+    time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ReleaseData {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    account_id: String,
+    hold_id: String,
+    time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockedCategoriesData {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    account_id: String,
+    blocked_categories: Vec<MerchantCategory>,
+}
+
 #[cfg(test)]
 mod account_data {
     use super::*;
@@ -52,12 +350,37 @@ mod account_data {
     #[test]
     fn create_account() {
         let account_data = AccountData {
-            available_limit: 100,
+            account_id: String::from("a"),
+            available_limit: Decimal::from(100),
+            currency: None,
             active_card: true,
+            blocked_categories: vec![],
+            daily_limit: None,
+            monthly_limit: None,
         };
         let expected_account = Account::new(100, true, vec![]);
 
-        assert_eq!(account_data.to_account(), expected_account);
+        assert_eq!(account_data.into_account(), Ok(expected_account));
+    }
+
+    #[test]
+    fn create_account_with_decimal_limit() {
+        let account_data = AccountData {
+            account_id: String::from("a"),
+            available_limit: "1500.5".parse().unwrap(),
+            currency: Currency::from_code("EUR"),
+            active_card: true,
+            blocked_categories: vec![],
+            daily_limit: None,
+            monthly_limit: None,
+        };
+        let expected_account = Account::new(
+            Money::new(150050, Currency::from_code("EUR").unwrap()),
+            true,
+            vec![],
+        );
+
+        assert_eq!(account_data.into_account(), Ok(expected_account));
     }
 }
 
@@ -74,12 +397,35 @@ mod tx_data {
             .into();
 
         let tx_data = TxData {
-            amount: 100,
+            account_id: String::from("a"),
+            id: Some(String::from("tx-1")),
+            amount: Decimal::from(100),
+            currency: None,
             merchant: String::from("Nike"),
             time: tx_time,
+            mcc: None,
+            converted: None,
+            rejected: None,
+        };
+        let expected_tx = TX::new(100, "Nike", tx_time).with_id(Some(String::from("tx-1")));
+
+        assert_eq!(tx_data.into_tx(), Ok(expected_tx));
+    }
+
+    #[test]
+    fn reject_tx_with_too_many_decimal_places() {
+        let tx_data = TxData {
+            account_id: String::from("a"),
+            id: None,
+            amount: "10.5".parse().unwrap(),
+            currency: Currency::from_code("JPY"),
+            merchant: String::from("Nike"),
+            time: DateTime::default(),
+            mcc: None,
+            converted: None,
+            rejected: None,
         };
-        let expected_tx = TX::new(100, "Nike", tx_time);
 
-        assert_eq!(tx_data.to_tx(), expected_tx);
+        assert_eq!(tx_data.into_tx(), Err(OperationError::InvalidAmount));
     }
 }
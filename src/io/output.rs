@@ -0,0 +1,350 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::{ser, Serialize, Serializer};
+use serde_json::value::RawValue;
+
+use crate::account::{AccountState, Currency, Decimal, Money, RuleTrace, ShadowSummary, Violation};
+use crate::io::{InvalidOperation, OperationResult};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// One compact `{"account": {...}, "violations": [...]}` json line per result.
+    #[default]
+    Envelope,
+    /// The flat account state, pretty printed across multiple lines.
+    Pretty,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+struct AccountOutput<'a> {
+    #[serde(skip_serializing_if = "str::is_empty")]
+    account_id: &'a str,
+    active_card: bool,
+    #[serde(serialize_with = "serialize_number")]
+    available_limit: Decimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: Option<Currency>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct StateOutput<'a> {
+    account: AccountOutput<'a>,
+    violations: &'a [Violation],
+    #[serde(
+        rename = "converted-amount",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_number"
+    )]
+    converted_amount: Option<Decimal>,
+    #[serde(rename = "shadow-violations", skip_serializing_if = "<[_]>::is_empty")]
+    shadow_violations: &'a [Violation],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    trace: &'a [RuleTrace],
+}
+
+impl<'a> From<&'a AccountState> for StateOutput<'a> {
+    fn from(state: &'a AccountState) -> Self {
+        Self {
+            account: AccountOutput {
+                account_id: state.account_id(),
+                active_card: state.active_card(),
+                available_limit: state.available_limit().to_decimal(),
+                currency: non_default_currency(state),
+            },
+            violations: state.violations(),
+            converted_amount: state.converted_amount().map(Money::to_decimal),
+            shadow_violations: state.shadow_violations(),
+            trace: state.trace(),
+        }
+    }
+}
+
+/// The account state with its amounts as bare numbers, as printed by the pretty format.
+#[derive(Debug, Serialize, PartialEq)]
+struct FlatStateOutput<'a> {
+    #[serde(skip_serializing_if = "str::is_empty")]
+    account_id: &'a str,
+    active_card: bool,
+    #[serde(serialize_with = "serialize_number")]
+    available_limit: Decimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: Option<Currency>,
+    violations: &'a [Violation],
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_number"
+    )]
+    converted_amount: Option<Decimal>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    shadow_violations: &'a [Violation],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    trace: &'a [RuleTrace],
+}
+
+impl<'a> From<&'a AccountState> for FlatStateOutput<'a> {
+    fn from(state: &'a AccountState) -> Self {
+        Self {
+            account_id: state.account_id(),
+            active_card: state.active_card(),
+            available_limit: state.available_limit().to_decimal(),
+            currency: non_default_currency(state),
+            violations: state.violations(),
+            converted_amount: state.converted_amount().map(Money::to_decimal),
+            shadow_violations: state.shadow_violations(),
+            trace: state.trace(),
+        }
+    }
+}
+
+/// Currency of the account amounts, left out when it's the default one.
+fn non_default_currency(state: &AccountState) -> Option<Currency> {
+    let currency = state.available_limit().currency();
+
+    (currency != Currency::default()).then_some(currency)
+}
+
+/// Prints amounts as json numbers with their exact decimal digits, while they are stored as
+/// decimal strings.
+fn serialize_number<S: Serializer>(amount: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+    RawValue::from_string(amount.to_string())
+        .map_err(ser::Error::custom)?
+        .serialize(serializer)
+}
+
+fn serialize_optional_number<S: Serializer>(
+    amount: &Option<Decimal>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match amount {
+        Some(amount) => serialize_number(amount, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct ErrorOutput<'a> {
+    error: &'a InvalidOperation,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+struct ShadowSummaryOutput<'a> {
+    shadow_summary: &'a ShadowSummary,
+}
+
+pub fn format_result(result: &OperationResult, format: OutputFormat) -> Result<String> {
+    match result {
+        OperationResult::Processed(state) => format_state(state, format),
+        OperationResult::Invalid(error) => format_error(error, format),
+    }
+}
+
+fn format_error(error: &InvalidOperation, format: OutputFormat) -> Result<String> {
+    let output = ErrorOutput { error };
+
+    let output = match format {
+        OutputFormat::Envelope => serde_json::to_string(&output)?,
+        OutputFormat::Pretty => serde_json::to_string_pretty(&output)?,
+    };
+
+    Ok(output)
+}
+
+pub fn format_shadow_summary(summary: &ShadowSummary, format: OutputFormat) -> Result<String> {
+    let output = ShadowSummaryOutput {
+        shadow_summary: summary,
+    };
+
+    let output = match format {
+        OutputFormat::Envelope => serde_json::to_string(&output)?,
+        OutputFormat::Pretty => serde_json::to_string_pretty(&output)?,
+    };
+
+    Ok(output)
+}
+
+pub fn format_state(state: &AccountState, format: OutputFormat) -> Result<String> {
+    let output = match format {
+        OutputFormat::Envelope => serde_json::to_string(&StateOutput::from(state))?,
+        OutputFormat::Pretty => serde_json::to_string_pretty(&FlatStateOutput::from(state))?,
+    };
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod state_output {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::account::{Currency, OperationError};
+
+    #[test]
+    fn format_envelope() {
+        let state = AccountState::new(true, 80, vec![OperationError::InsufficientLimit]);
+
+        let output = format_state(&state, OutputFormat::Envelope).unwrap();
+
+        assert_eq!(
+            output,
+            "{\"account\":{\"active-card\":true,\"available-limit\":80},\"violations\":[\"insufficient-limit\"]}"
+        );
+    }
+
+    #[test]
+    fn format_envelope_with_account_id() {
+        let state = AccountState::new(true, 80, vec![]).with_account_id("a");
+
+        let output = format_state(&state, OutputFormat::Envelope).unwrap();
+
+        assert_eq!(
+            output,
+            "{\"account\":{\"account-id\":\"a\",\"active-card\":true,\"available-limit\":80},\"violations\":[]}"
+        );
+    }
+
+    #[test]
+    fn format_decimal_limit() {
+        let state = AccountState::new(true, Money::new(8050, Currency::USD), vec![]);
+
+        let envelope = format_state(&state, OutputFormat::Envelope).unwrap();
+        let pretty = format_state(&state, OutputFormat::Pretty).unwrap();
+
+        assert_eq!(
+            envelope,
+            "{\"account\":{\"active-card\":true,\"available-limit\":80.5},\"violations\":[]}"
+        );
+        assert_eq!(
+            pretty,
+            "{\n  \"active_card\": true,\n  \"available_limit\": 80.5,\n  \"violations\": []\n}"
+        );
+    }
+
+    #[test]
+    fn format_exact_limit_with_currency() {
+        let eur = Currency::from_code("EUR").unwrap();
+        let state = AccountState::new(true, Money::new(123456789012345678, eur), vec![])
+            .with_converted_amount(Money::new(2222, eur));
+
+        let envelope = format_state(&state, OutputFormat::Envelope).unwrap();
+        let pretty = format_state(&state, OutputFormat::Pretty).unwrap();
+
+        assert_eq!(
+            envelope,
+            "{\"account\":{\"active-card\":true,\"available-limit\":1234567890123456.78,\"currency\":\"EUR\"},\"violations\":[],\"converted-amount\":22.22}"
+        );
+        assert_eq!(
+            pretty,
+            "{\n  \"active_card\": true,\n  \"available_limit\": 1234567890123456.78,\n  \"currency\": \"EUR\",\n  \"violations\": [],\n  \"converted_amount\": 22.22\n}"
+        );
+    }
+
+    #[test]
+    fn format_converted_amount() {
+        let state = AccountState::new(true, 80, vec![]).with_converted_amount(Money::from(20));
+
+        let envelope = format_state(&state, OutputFormat::Envelope).unwrap();
+        let pretty = format_state(&state, OutputFormat::Pretty).unwrap();
+
+        assert_eq!(
+            envelope,
+            "{\"account\":{\"active-card\":true,\"available-limit\":80},\"violations\":[],\"converted-amount\":20}"
+        );
+        assert_eq!(
+            pretty,
+            "{\n  \"active_card\": true,\n  \"available_limit\": 80,\n  \"violations\": [],\n  \"converted_amount\": 20\n}"
+        );
+    }
+
+    #[test]
+    fn format_custom_violation() {
+        let state = AccountState::from_violations(
+            true,
+            80,
+            vec![
+                Violation::from(OperationError::DuplicatedTx),
+                Violation::new("merchant-velocity").with_detail(serde_json::json!({"count": 3})),
+            ],
+        );
+
+        let output = format_state(&state, OutputFormat::Envelope).unwrap();
+
+        assert_eq!(
+            output,
+            "{\"account\":{\"active-card\":true,\"available-limit\":80},\"violations\":[\"duplicated-tx\",{\"code\":\"merchant-velocity\",\"detail\":{\"count\":3}}]}"
+        );
+    }
+
+    #[test]
+    fn format_trace() {
+        let state = AccountState::new(true, 80, vec![OperationError::InsufficientLimit])
+            .with_trace(vec![
+                RuleTrace::new(
+                    "insufficient-limit",
+                    Some(OperationError::InsufficientLimit.into()),
+                )
+                .with_evidence(serde_json::json!({"available-limit": 80})),
+                RuleTrace::not_run("duplicated-tx"),
+            ]);
+
+        let output = format_state(&state, OutputFormat::Envelope).unwrap();
+
+        assert_eq!(
+            output,
+            "{\"account\":{\"active-card\":true,\"available-limit\":80},\"violations\":[\"insufficient-limit\"],\"trace\":[{\"rule\":\"insufficient-limit\",\"ran\":true,\"violation\":\"insufficient-limit\",\"evidence\":{\"available-limit\":80}},{\"rule\":\"duplicated-tx\",\"ran\":false,\"violation\":null}]}"
+        );
+    }
+
+    #[test]
+    fn format_shadow_violations() {
+        let state = AccountState::new(true, 80, vec![])
+            .with_shadow_violations(vec![Violation::new("merchant-velocity")]);
+
+        let output = format_state(&state, OutputFormat::Envelope).unwrap();
+
+        assert_eq!(
+            output,
+            "{\"account\":{\"active-card\":true,\"available-limit\":80},\"violations\":[],\"shadow-violations\":[\"merchant-velocity\"]}"
+        );
+    }
+
+    #[test]
+    fn format_summary() {
+        let mut summary = ShadowSummary::default();
+        summary.record(&[Violation::new("merchant-velocity")], true);
+
+        let output = format_shadow_summary(&summary, OutputFormat::Envelope).unwrap();
+
+        assert_eq!(
+            output,
+            "{\"shadow-summary\":{\"checked-txs\":1,\"flagged-txs\":1,\"would-decline\":1,\"violations\":{\"merchant-velocity\":1}}}"
+        );
+    }
+
+    #[test]
+    fn format_pretty() {
+        let state = AccountState::new(true, 80, vec![]);
+
+        let output = format_state(&state, OutputFormat::Pretty).unwrap();
+
+        assert_eq!(
+            output,
+            "{\n  \"active_card\": true,\n  \"available_limit\": 80,\n  \"violations\": []\n}"
+        );
+    }
+
+    #[test]
+    fn format_invalid_operation() {
+        let error = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let result = OperationResult::Invalid(InvalidOperation::new(3, "{", &error));
+
+        let output = format_result(&result, OutputFormat::Envelope).unwrap();
+
+        assert_eq!(
+            output,
+            "{\"error\":{\"line\":3,\"input\":\"{\",\"message\":\"EOF while parsing an object at line 1 column 1\"}}"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::account::{Account, Authorizer};
+
+pub fn export_snapshot(authorizer: &Authorizer, path: &Path) -> Result<()> {
+    let accounts: BTreeMap<&String, &Account> = authorizer.accounts().iter().collect();
+
+    let content = serde_json::to_string_pretty(&accounts)?;
+
+    fs::write(path, content)
+        .with_context(|| format!("Could not write snapshot '{}'", path.display()))
+}
+
+/// Loads every account of the snapshot at `path` into the authorizer, returning how many were
+/// imported.
+pub fn import_snapshot(authorizer: &mut Authorizer, path: &Path) -> Result<usize> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Could not read snapshot '{}'", path.display()))?;
+
+    let accounts: BTreeMap<String, Account> = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid snapshot '{}'", path.display()))?;
+
+    let imported = accounts.len();
+
+    accounts
+        .into_iter()
+        .for_each(|(account_id, account)| authorizer.restore_account(account_id, account));
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod account_snapshot {
+    use super::*;
+
+    use chrono::DateTime;
+    use pretty_assertions::assert_eq;
+
+    use crate::account::{AccountState, Currency, Money, RulesConfig, TX};
+
+    #[test]
+    fn export_and_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot.json");
+
+        let mut authorizer = Authorizer::new();
+        authorizer.create_account(String::from("a"), Account::new(100, true, vec![]));
+        authorizer.register_tx(
+            "a",
+            TX::new(20, "Merchant X", DateTime::default()).with_id(Some(String::from("tx-1"))),
+        );
+
+        export_snapshot(&authorizer, &path).unwrap();
+
+        let mut restored_authorizer = Authorizer::new();
+        let imported = import_snapshot(&mut restored_authorizer, &path).unwrap();
+
+        assert_eq!(imported, 1);
+        assert_eq!(restored_authorizer.accounts(), authorizer.accounts());
+    }
+
+    #[test]
+    fn export_and_import_processed_txs_in_account_currency() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot.json");
+        let kwd = Currency::from_code("KWD").unwrap();
+
+        let mut authorizer = Authorizer::new();
+        authorizer.create_account(
+            String::from("a"),
+            Account::new(Money::new(1_000_000, kwd), true, vec![]),
+        );
+        authorizer.register_tx(
+            "a",
+            TX::new(Money::new(19_625, kwd), "Merchant X", DateTime::default())
+                .with_id(Some(String::from("tx-1"))),
+        );
+
+        export_snapshot(&authorizer, &path).unwrap();
+
+        let mut restored_authorizer = Authorizer::new();
+        import_snapshot(&mut restored_authorizer, &path).unwrap();
+
+        assert_eq!(restored_authorizer.accounts(), authorizer.accounts());
+
+        let replayed = restored_authorizer.register_tx(
+            "a",
+            TX::new(Money::new(19_625, kwd), "Merchant X", DateTime::default())
+                .with_id(Some(String::from("tx-1"))),
+        );
+
+        assert_eq!(
+            replayed,
+            AccountState::new(true, Money::new(980_375, kwd), vec![]).with_account_id("a")
+        );
+    }
+
+    #[test]
+    fn import_handwritten_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot.json");
+        fs::write(
+            &path,
+            "{\"a\": {
+                \"available-limit\": 80,
+                \"active-card\": true,
+                \"txs\": [{\"merchant\": \"Merchant X\", \"amount\": 20, \"time\": \"2019-02-13T10:00:00Z\"}],
+                \"rules\": {\"duplicated-tx\": {\"enabled\": false}}
+            }}",
+        )
+        .unwrap();
+
+        let mut authorizer = Authorizer::new();
+        import_snapshot(&mut authorizer, &path).unwrap();
+
+        let mut rules_config = RulesConfig::default();
+        rules_config.duplicated_tx.enabled = false;
+
+        let expected_account = Account::new(
+            80,
+            true,
+            vec![TX::new(
+                20,
+                "Merchant X",
+                DateTime::parse_from_rfc3339("2019-02-13T10:00:00.000Z")
+                    .unwrap()
+                    .into(),
+            )],
+        )
+        .with_rules_config(rules_config);
+
+        assert_eq!(authorizer.accounts().get("a"), Some(&expected_account));
+
+        let state = authorizer.register_tx(
+            "a",
+            TX::new(
+                20,
+                "Merchant X",
+                DateTime::parse_from_rfc3339("2019-02-13T10:00:30.000Z")
+                    .unwrap()
+                    .into(),
+            ),
+        );
+
+        assert_eq!(
+            state,
+            AccountState::new(true, 60, vec![]).with_account_id("a")
+        );
+    }
+
+    #[test]
+    fn reject_invalid_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot.json");
+        fs::write(&path, "{\"a\": {\"available-limit\": 80}}").unwrap();
+
+        assert!(import_snapshot(&mut Authorizer::new(), &path).is_err());
+    }
+}
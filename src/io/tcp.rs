@@ -0,0 +1,171 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{Context, Result};
+
+use crate::io::{
+    format_result, format_shadow_summary, lock_executor, process_operation, OutputFormat,
+    SharedExecutor,
+};
+
+/// Line answered with the shadow summary instead of being parsed as an operation.
+const SHADOW_SUMMARY_COMMAND: &str = "shadow-summary";
+
+/// Accepts json lines operations from every connection to `listener`, answering each line with
+/// its result, or the `shadow-summary` command with the shadow summary. All connections share
+/// the same executor.
+pub fn serve(listener: TcpListener, executor: SharedExecutor, format: OutputFormat) -> Result<()> {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                eprintln!("Could not accept connection: {}", error);
+                continue;
+            }
+        };
+
+        let mut executor = Arc::clone(&executor);
+
+        thread::spawn(move || {
+            if let Err(error) = handle_connection(stream, &mut executor, format) {
+                eprintln!("Connection closed with error: {:#}", error);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    executor: &mut SharedExecutor,
+    format: OutputFormat,
+) -> Result<()> {
+    let reader = BufReader::new(stream.try_clone().context("Could not read connection")?);
+    let mut writer = stream;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.context("Could not read operation")?;
+
+        let mut output = if line.trim() == SHADOW_SUMMARY_COMMAND {
+            let executor = lock_executor(executor)?;
+
+            format_shadow_summary(executor.authorizer().shadow_summary(), format)?
+        } else {
+            match process_operation(index + 1, &line, executor, true)? {
+                Some(result) => format_result(&result, format)?,
+                None => continue,
+            }
+        };
+        output.push('\n');
+
+        writer
+            .write_all(output.as_bytes())
+            .context("Could not write result")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tcp_server {
+    use super::*;
+
+    use std::io::Lines;
+    use std::sync::Mutex;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::account::Authorizer;
+    use crate::io::OperationExecutor;
+
+    fn start_server() -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let executor = Arc::new(Mutex::new(OperationExecutor::new(Authorizer::new())));
+
+        thread::spawn(move || serve(listener, executor, OutputFormat::Envelope));
+
+        address
+    }
+
+    fn send(
+        stream: &mut TcpStream,
+        responses: &mut Lines<BufReader<TcpStream>>,
+        line: &str,
+    ) -> String {
+        stream.write_all(format!("{}\n", line).as_bytes()).unwrap();
+        responses.next().unwrap().unwrap()
+    }
+
+    #[test]
+    fn share_accounts_between_connections() {
+        let address = start_server();
+
+        let mut first = TcpStream::connect(address).unwrap();
+        let mut first_responses = BufReader::new(first.try_clone().unwrap()).lines();
+        let mut second = TcpStream::connect(address).unwrap();
+        let mut second_responses = BufReader::new(second.try_clone().unwrap()).lines();
+
+        let created = send(
+            &mut first,
+            &mut first_responses,
+            "{\"account\": {\"active-card\": true, \"available-limit\": 100}}",
+        );
+        let authorized = send(
+            &mut second,
+            &mut second_responses,
+            "{\"transaction\": {\"merchant\": \"Burger King\", \"amount\": 20, \"time\": \"2019-02-13T10:00:00.000Z\"}}",
+        );
+
+        assert_eq!(
+            created,
+            "{\"account\":{\"active-card\":true,\"available-limit\":100},\"violations\":[]}"
+        );
+        assert_eq!(
+            authorized,
+            "{\"account\":{\"active-card\":true,\"available-limit\":80},\"violations\":[]}"
+        );
+    }
+
+    #[test]
+    fn answer_invalid_operations_with_errors() {
+        let address = start_server();
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut responses = BufReader::new(stream.try_clone().unwrap()).lines();
+
+        let error = send(&mut stream, &mut responses, "nope");
+        let state = send(
+            &mut stream,
+            &mut responses,
+            "{\"account\": {\"active-card\": true, \"available-limit\": 100}}",
+        );
+
+        assert_eq!(
+            error,
+            "{\"error\":{\"line\":1,\"input\":\"nope\",\"message\":\"expected value at line 1 column 1\"}}"
+        );
+        assert_eq!(
+            state,
+            "{\"account\":{\"active-card\":true,\"available-limit\":100},\"violations\":[]}"
+        );
+    }
+
+    #[test]
+    fn answer_shadow_summary_command() {
+        let address = start_server();
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut responses = BufReader::new(stream.try_clone().unwrap()).lines();
+
+        let summary = send(&mut stream, &mut responses, "shadow-summary");
+
+        assert_eq!(
+            summary,
+            "{\"shadow-summary\":{\"checked-txs\":0,\"flagged-txs\":0,\"would-decline\":0,\"violations\":{}}}"
+        );
+    }
+}
@@ -0,0 +1,8 @@
+//! Credit card transaction authorizer, processing account and transaction operations against
+//! a set of business rules.
+//!
+//! Rules of your own can be registered on top of the configured ones with
+//! [`Authorizer::builder`](account::Authorizer::builder).
+
+pub mod account;
+pub mod io;
@@ -1,33 +1,188 @@
-mod account;
-mod io;
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, Subcommand};
+use std::fs::File;
+use std::io::BufReader;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
 
-use anyhow::{Context, Result};
-use clap::Parser;
-use std::fs;
+use cc_authorizer::account::Authorizer;
+use cc_authorizer::io;
 
 /// Cli arguments structure.
 #[derive(Parser)]
 struct Cli {
-    /// The path to the file to read.
-    path: std::path::PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The path to the file to read. Operations are read from stdin when omitted.
+    path: Option<std::path::PathBuf>,
+
+    /// The format used to print each processed operation result.
+    #[arg(long, value_enum, default_value_t, global = true)]
+    format: io::OutputFormat,
+
+    /// A .toml or .json file enabling, disabling and tuning the authorizer rules.
+    #[arg(long, global = true)]
+    config: Option<std::path::PathBuf>,
+
+    /// A .toml or .json table of exchange rates to convert foreign-currency transactions with.
+    #[arg(long, global = true)]
+    fx_rates: Option<std::path::PathBuf>,
+
+    /// A file of rules written in the rules language, checked after the built-in rules.
+    #[arg(long, global = true)]
+    rules_file: Option<std::path::PathBuf>,
+
+    /// A file of rules run in shadow mode, reporting violations without declining transactions.
+    #[arg(long, global = true)]
+    shadow_rules_file: Option<std::path::PathBuf>,
+
+    /// Attach the verdict of every rule, and the evidence behind it, to each transaction result.
+    #[arg(long, global = true)]
+    explain: bool,
+
+    /// Report unparseable operations as error records instead of aborting the run.
+    #[arg(long)]
+    continue_on_error: bool,
+
+    /// Journal file of accepted operations, replayed on startup to restore the accounts state.
+    #[arg(long, global = true)]
+    journal: Option<std::path::PathBuf>,
+
+    /// Snapshot of accounts to load before processing any operation.
+    #[arg(long, global = true)]
+    import_snapshot: Option<std::path::PathBuf>,
+
+    /// File where a snapshot of every account is written once all operations are processed.
+    #[arg(long)]
+    export_snapshot: Option<std::path::PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Listen for json lines operations on a local TCP port, answering each line with its result.
+    Serve {
+        /// The local port to listen on.
+        #[arg(long, default_value_t = 7878)]
+        port: u16,
+    },
+    /// Serve the authorizer as an HTTP/JSON API on a local port.
+    Http {
+        /// The local port to listen on.
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
 }
 
 fn main() -> Result<()> {
     let args = Cli::parse();
 
-    let file_content = fs::read_to_string(&args.path)
-        .with_context(|| format!("Could not read file '{}'", &args.path.to_str().unwrap()))?;
+    let mut builder = Authorizer::builder();
+
+    if let Some(path) = &args.config {
+        let config = io::load_config(path)?;
+
+        for warning in io::config_warnings(&config) {
+            eprintln!("Warning: {}", warning);
+        }
+
+        builder = builder.config(config);
+    }
+
+    if let Some(path) = &args.fx_rates {
+        builder = builder.fx_rates(io::load_fx_rates(path)?);
+    }
+
+    if let Some(path) = &args.rules_file {
+        for rule in io::load_rules(path)? {
+            builder = builder.rule(rule);
+        }
+    }
 
-    let results = io::parse_file(file_content).with_context(|| {
-        format!(
-            "Could not parse file operation for file '{}'",
-            &args.path.to_str().unwrap()
+    if let Some(path) = &args.shadow_rules_file {
+        for rule in io::load_rules(path)? {
+            builder = builder.shadow_rule(rule);
+        }
+    }
+
+    let mut authorizer = builder.explain(args.explain).build();
+
+    if let Some(path) = &args.import_snapshot {
+        io::import_snapshot(&mut authorizer, path)?;
+    }
+
+    let mut executor = io::OperationExecutor::new(authorizer);
+
+    if let Some(path) = &args.journal {
+        executor.open_journal(path)?;
+    }
+
+    match &args.command {
+        Some(Command::Serve { port }) => serve(executor, *port, args.format),
+        Some(Command::Http { port }) => serve_http(executor, *port, args.format),
+        None => process(executor, &args),
+    }
+}
+
+fn serve(executor: io::OperationExecutor, port: u16, format: io::OutputFormat) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .with_context(|| format!("Could not listen on port {}", port))?;
+
+    eprintln!("Listening on {}", listener.local_addr()?);
+
+    io::serve(listener, Arc::new(Mutex::new(executor)), format)
+}
+
+fn serve_http(executor: io::OperationExecutor, port: u16, format: io::OutputFormat) -> Result<()> {
+    let server = tiny_http::Server::http(("127.0.0.1", port))
+        .map_err(|error| anyhow!("Could not listen on port {}: {}", port, error))?;
+
+    eprintln!("Listening on http://{}", server.server_addr());
+
+    io::serve_http(server, Arc::new(Mutex::new(executor)), format)
+}
+
+fn process(mut executor: io::OperationExecutor, args: &Cli) -> Result<()> {
+    let print_result = |result: io::OperationResult| {
+        println!("{}", io::format_result(&result, args.format)?);
+        Ok(())
+    };
+
+    match &args.path {
+        Some(path) => {
+            let file = File::open(path)
+                .with_context(|| format!("Could not read file '{}'", path.display()))?;
+
+            io::parse_operations(
+                BufReader::new(file),
+                &mut executor,
+                args.continue_on_error,
+                print_result,
+            )
+            .with_context(|| {
+                format!(
+                    "Could not parse file operation for file '{}'",
+                    path.display()
+                )
+            })
+        }
+        None => io::parse_operations(
+            std::io::stdin().lock(),
+            &mut executor,
+            args.continue_on_error,
+            print_result,
         )
-    })?;
+        .context("Could not parse operation from stdin"),
+    }?;
+
+    if let Some(path) = &args.export_snapshot {
+        io::export_snapshot(executor.authorizer(), path)?;
+    }
 
-    results
-        .into_iter()
-        .for_each(|r| println!("{}", serde_json::to_string_pretty(&r).unwrap()));
+    if executor.authorizer().has_shadow_rules() {
+        let summary = executor.authorizer().shadow_summary();
+        eprintln!("{}", io::format_shadow_summary(summary, args.format)?);
+    }
 
     Ok(())
 }